@@ -0,0 +1,108 @@
+use super::{parse::*, primitive::*, result::*, value::*};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+// identifiers get turned into numbers once, so the vm never has to hash a string
+#[derive(Default)]
+struct Interner {
+    ids: HashMap<String, u32>,
+    names: Vec<String>,
+}
+
+thread_local! {
+    static INTERNER: RefCell<Interner> = RefCell::new(Interner::default());
+}
+
+pub(crate) fn intern(name: &str) -> u32 {
+    INTERNER.with_borrow_mut(|interner| {
+        if let Some(&id) = interner.ids.get(name) {
+            return id;
+        }
+        let id = interner.names.len() as u32;
+        interner.ids.insert(name.into(), id);
+        interner.names.push(name.into());
+        id
+    })
+}
+
+pub(crate) fn symbol_name(id: u32) -> String {
+    INTERNER.with_borrow(|interner| interner.names[id as usize].clone())
+}
+
+#[derive(Clone, Copy, Debug)]
+pub(crate) enum Instr {
+    /// pushes a value from the constant pool
+    Push(usize),
+    /// pushes the value bound to an (interned) identifier
+    Load(u32),
+    /// pops a value into an (interned) identifier
+    Store(u32),
+    /// captures the current scope into a function from the function pool
+    Closure(usize),
+    /// runs a function from the function pool on a new stack, in the current scope, and pushes
+    /// what it left as a list
+    List(usize),
+    Primitive(Primitive),
+    /// `!`. `name` is the identifier that was loaded right before, for traces
+    Call {
+        tail: bool,
+        name: Option<u32>,
+    },
+}
+
+/// a compiled function body
+pub(crate) struct Chunk {
+    pub(crate) code: Vec<Instr>,
+    pub(crate) spans: Vec<Span>, // one per instruction
+    pub(crate) constants: Vec<Value>,
+    pub(crate) functions: Vec<Rc<Function>>, // function literals (and list bodies), never copied
+}
+
+pub(crate) fn compile(ast: &AST) -> Chunk {
+    let mut chunk = Chunk {
+        code: Vec::with_capacity(ast.len()),
+        spans: Vec::with_capacity(ast.len()),
+        constants: vec![],
+        functions: vec![],
+    };
+    let mut last_load: Option<u32> = None;
+    for (i, Node { statement, span }) in ast.iter().enumerate() {
+        let instr = match statement {
+            Statement::Binding(id) => Instr::Store(intern(id)),
+            Statement::Identifier(id) => Instr::Load(intern(id)),
+            Statement::Literal(v) => {
+                chunk.constants.push(v.clone());
+                Instr::Push(chunk.constants.len() - 1)
+            }
+            Statement::Function(f) => {
+                chunk.functions.push(f.clone());
+                Instr::Closure(chunk.functions.len() - 1)
+            }
+            Statement::List(body) => {
+                chunk.functions.push(body.clone());
+                Instr::List(chunk.functions.len() - 1)
+            }
+            // same rule as the tree walker: a ! is a tail call when nothing comes after it
+            Statement::Primitive(Primitive::Call) => Instr::Call {
+                tail: i == ast.len() - 1,
+                name: last_load,
+            },
+            // ? is "pick a function" and then a regular call
+            Statement::Primitive(Primitive::If) => {
+                chunk.code.push(Instr::Primitive(Primitive::If));
+                chunk.spans.push(*span);
+                Instr::Call {
+                    tail: i == ast.len() - 1,
+                    name: None,
+                }
+            }
+            Statement::Primitive(p) => Instr::Primitive(*p),
+        };
+        last_load = match instr {
+            Instr::Load(id) => Some(id),
+            _ => None,
+        };
+        chunk.code.push(instr);
+        chunk.spans.push(*span);
+    }
+    chunk
+}
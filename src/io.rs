@@ -0,0 +1,121 @@
+use super::{result::*, value::*};
+use std::{
+    cell::RefCell,
+    io::{BufRead, BufReader, Write, stdin, stdout},
+    rc::Rc,
+};
+
+/// where `↗` writes to and `⍞⍗⍠` read from
+pub(crate) struct Io {
+    input: Box<dyn BufRead>,
+    output: Box<dyn Write>,
+}
+
+impl Default for Io {
+    fn default() -> Self {
+        Io {
+            input: Box::new(BufReader::new(stdin())),
+            output: Box::new(stdout()),
+        }
+    }
+}
+
+impl Io {
+    pub(crate) fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.input = Box::new(input);
+    }
+
+    pub(crate) fn set_output(&mut self, output: impl Write + 'static) {
+        self.output = Box::new(output);
+    }
+
+    pub(crate) fn print(&mut self, value: &Value) -> JSLResult<()> {
+        write!(self.output, "{value}").map_err(|e| JSLError::new(format!("could not print: {e}")))
+    }
+
+    pub(crate) fn flush(&mut self) -> JSLResult<()> {
+        self.output
+            .flush()
+            .map_err(|e| JSLError::new(format!("could not flush output: {e}")))
+    }
+
+    // everything printed so far has to show up before we wait for input, or prompts won't
+    pub(crate) fn read_line(&mut self) -> JSLResult<Option<String>> {
+        self.flush()?;
+        let mut line = String::new();
+        if self.input.read_line(&mut line).map_err(input_error)? == 0 {
+            return Ok(None);
+        }
+        // the newline isn't part of the line
+        if line.ends_with('\n') {
+            line.pop();
+            if line.ends_with('\r') {
+                line.pop();
+            }
+        }
+        Ok(Some(line))
+    }
+
+    pub(crate) fn read_all(&mut self) -> JSLResult<String> {
+        self.flush()?;
+        let mut all = String::new();
+        self.input.read_to_string(&mut all).map_err(input_error)?;
+        Ok(all)
+    }
+
+    // reads exactly one utf-8 character, without reading past it (so ⍞ still gets the rest of the line)
+    pub(crate) fn read_char(&mut self) -> JSLResult<Option<char>> {
+        self.flush()?;
+        let mut bytes = [0; 4];
+        if self.input.read(&mut bytes[..1]).map_err(input_error)? == 0 {
+            return Ok(None);
+        }
+        // the first byte says how long the character is. if it can't start one at all, don't
+        // eat whatever comes after it
+        let len = match bytes[0] {
+            0x00..=0x7f => 1,
+            0xc0..=0xdf => 2,
+            0xe0..=0xef => 3,
+            0xf0..=0xf7 => 4,
+            _ => return Err(invalid_utf8()),
+        };
+        self.input
+            .read_exact(&mut bytes[1..len])
+            .map_err(input_error)?;
+        match std::str::from_utf8(&bytes[..len]) {
+            Ok(s) => Ok(s.chars().next()),
+            Err(_) => Err(invalid_utf8()),
+        }
+    }
+}
+
+fn input_error(e: std::io::Error) -> JSLError {
+    JSLError::new(format!("could not read input: {e}"))
+}
+
+fn invalid_utf8() -> JSLError {
+    JSLError::new("could not read input: stream did not contain valid UTF-8")
+}
+
+/// somewhere to send the output of an [`Interpreter`](crate::Interpreter) to, instead of stdout.
+/// it's a shared handle, so keep a clone around to look at what got printed
+#[derive(Clone, Default)]
+pub struct OutputBuffer(Rc<RefCell<Vec<u8>>>);
+
+impl OutputBuffer {
+    /// everything printed since the last time this was called
+    pub fn take(&self) -> String {
+        String::from_utf8_lossy(&self.0.take()).into_owned()
+    }
+}
+
+impl Write for OutputBuffer {
+    fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+        self.0.borrow_mut().extend_from_slice(buf);
+        Ok(buf.len())
+    }
+
+    fn flush(&mut self) -> std::io::Result<()> {
+        Ok(())
+    }
+}
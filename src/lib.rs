@@ -0,0 +1,176 @@
+//! JSL, a toy stack language, as a library.
+//!
+//! Everything goes through an [`Interpreter`], which holds on to a stack and the top-level
+//! bindings between calls to [`Interpreter::eval`]:
+//!
+//! ```
+//! use jsl::{Interpreter, Value};
+//!
+//! let mut jsl = Interpreter::new();
+//! jsl.set("x", Value::Number(20.0));
+//! jsl.eval("x 1 + →y").unwrap();
+//! jsl.push(Value::Number(2.0));
+//! jsl.eval("y ×").unwrap();
+//! assert_eq!(jsl.pop(), Some(Value::Number(42.0)));
+//! ```
+//!
+//! Rust functions can be handed to JSL code too, see [`Interpreter::register`].
+#![warn(missing_docs)]
+
+mod compile;
+mod io;
+mod parse;
+mod primitive;
+mod result;
+mod run;
+mod scope;
+mod value;
+mod vm;
+
+use std::{
+    io::{BufRead, Write},
+    rc::Rc,
+};
+
+pub use io::OutputBuffer;
+pub use result::{JSLError, JSLResult, Span, TraceFrame};
+pub use value::{Closure, Key, NativeFunction, Value};
+
+/// runs JSL code, keeping the stack and the top-level bindings around between runs
+pub struct Interpreter {
+    engine: run::Engine,
+    stack: Vec<Value>,
+}
+
+impl Default for Interpreter {
+    fn default() -> Self {
+        Interpreter::new()
+    }
+}
+
+impl Interpreter {
+    /// an interpreter that compiles code to bytecode and runs it on the vm
+    pub fn new() -> Self {
+        Interpreter {
+            engine: run::Engine::new(false),
+            stack: vec![],
+        }
+    }
+
+    /// an interpreter that walks the syntax tree instead. it should always behave just like
+    /// [`Interpreter::new`], only slower
+    pub fn tree_walker() -> Self {
+        Interpreter {
+            engine: run::Engine::new(true),
+            stack: vec![],
+        }
+    }
+
+    /// runs some code on the current stack. if it errors, whatever it did before
+    /// the error (to the stack and to bindings) stays done. output gets flushed once it's done
+    pub fn eval(&mut self, code: &str) -> JSLResult<()> {
+        let ast = run::gen_ast_from_code(code)?;
+        self.run(ast)
+    }
+
+    /// same as [`Interpreter::eval`], but for code that starts at `start` inside of some bigger
+    /// source (like everything typed into a repl so far), so error spans point into that source
+    pub fn eval_at(&mut self, code: &str, start: Span) -> JSLResult<()> {
+        let ast = run::gen_ast_from_code_at(code, start)?;
+        self.run(ast)
+    }
+
+    fn run(&mut self, ast: parse::AST) -> JSLResult<()> {
+        let result = self.engine.run(ast, &mut self.stack);
+        // the program's own error is the more interesting one
+        let flushed = self.engine.io().flush();
+        result.and(flushed)
+    }
+
+    /// where `⍞⍗⍠` read from, instead of stdin
+    pub fn set_input(&mut self, input: impl BufRead + 'static) {
+        self.engine.io().set_input(input);
+    }
+
+    /// where `↗` writes to, instead of stdout. output only gets flushed before reading input
+    /// and at the end of every [`Interpreter::eval`], so this can be buffered. to get the
+    /// output back out, use an [`OutputBuffer`]:
+    ///
+    /// ```
+    /// use jsl::{Interpreter, OutputBuffer};
+    ///
+    /// let mut jsl = Interpreter::new();
+    /// let output = OutputBuffer::default();
+    /// jsl.set_output(output.clone());
+    /// jsl.eval("\"hi\" ↗").unwrap();
+    /// assert_eq!(output.take(), "hi");
+    /// ```
+    pub fn set_output(&mut self, output: impl Write + 'static) {
+        self.engine.io().set_output(output);
+    }
+
+    /// pushes a value on top of the stack
+    pub fn push(&mut self, value: Value) {
+        self.stack.push(value);
+    }
+
+    /// pops the value on top of the stack, if there is one
+    pub fn pop(&mut self) -> Option<Value> {
+        self.stack.pop()
+    }
+
+    /// the whole stack, bottom first
+    pub fn stack(&self) -> &[Value] {
+        &self.stack
+    }
+
+    /// removes everything from the stack
+    pub fn clear_stack(&mut self) {
+        self.stack.clear();
+    }
+
+    /// the value bound to a top-level identifier, or [`Value::Null`] if there isn't one
+    /// (just like reading an unbound identifier in code)
+    pub fn get(&self, name: &str) -> Value {
+        self.engine.globals().get_named(name)
+    }
+
+    /// binds a value to a top-level identifier, like `→name` does
+    pub fn set(&mut self, name: &str, value: Value) {
+        self.engine.globals().set_named(name, value);
+    }
+
+    /// binds a rust function to a top-level identifier, so code can call it with `name!`. it
+    /// gets the stack, pops its arguments and pushes its results, just like a jsl function:
+    ///
+    /// ```
+    /// use jsl::{Interpreter, JSLError, Value};
+    ///
+    /// let mut jsl = Interpreter::new();
+    /// jsl.register("hypot", |stack| match (stack.pop(), stack.pop()) {
+    ///     (Some(Value::Number(x)), Some(Value::Number(y))) => {
+    ///         stack.push(Value::Number(x.hypot(y)));
+    ///         Ok(())
+    ///     }
+    ///     _ => Err(JSLError::new("hypot needs two numbers")),
+    /// });
+    /// jsl.eval("3 4 hypot!").unwrap();
+    /// assert_eq!(jsl.pop(), Some(Value::Number(5.0)));
+    /// ```
+    pub fn register(
+        &mut self,
+        name: &str,
+        func: impl Fn(&mut Vec<Value>) -> JSLResult<()> + 'static,
+    ) {
+        self.set(
+            name,
+            Value::Native(Rc::new(NativeFunction::new(name, func))),
+        );
+    }
+}
+
+/// whether `code` can't be run yet because a function, list (or string) is still open,
+/// so a repl knows to keep reading lines
+pub fn is_incomplete(code: &str) -> bool {
+    parse::is_incomplete(code)
+}
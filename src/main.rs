@@ -1,41 +1,151 @@
-#![allow(dead_code)]
-
-use std::{collections::HashMap, fs};
-
-mod parse;
-mod primitive;
-mod result;
-mod run;
-mod value;
-
-use result::*;
-
-fn main_helper(input_file: String) -> JSLResult<()> {
-    let code = fs::read_to_string(input_file).or(Err(JSLError {
-        msg: "could not read file".into(),
-    }))?;
-    let ast: parse::AST;
-    match run::gen_ast_from_code(code.as_str()) {
-        Ok(good) => ast = good,
-        Err(e) => return Err(e),
+use std::{
+    fs,
+    io::{BufWriter, stdout},
+    process::ExitCode,
+};
+
+use jsl::{Interpreter, JSLError, JSLResult, Span, Value};
+use rustyline::{DefaultEditor, error::ReadlineError};
+
+// the script can see whatever came after its name on the command line as `args`
+fn main_helper(code: &str, tree_walk: bool, args: &[String]) -> JSLResult<()> {
+    let mut interpreter = new_interpreter(tree_walk, args);
+    // nobody is watching the output as it happens (unless the script asks for input, which
+    // flushes it), so there's no need to write it out bit by bit
+    interpreter.set_output(BufWriter::new(stdout()));
+    interpreter.eval(code)
+}
+
+fn new_interpreter(tree_walk: bool, args: &[String]) -> Interpreter {
+    let mut interpreter = if tree_walk {
+        Interpreter::tree_walker()
+    } else {
+        Interpreter::new()
+    };
+    let args = args.iter().map(|a| Value::string(a.as_str())).collect();
+    interpreter.set("args", Value::list(args));
+    interpreter
+}
+
+fn print_error(error: &JSLError, source: Option<&str>) {
+    eprint!("{}", error.report(source, true));
+}
+
+fn print_stack(stack: &[Value]) {
+    let mut line = String::new();
+    for value in stack {
+        line.push_str(format!("{value:?} ").as_str());
     }
-    run::run_ast(ast, &mut vec![], &mut HashMap::new())
+    println!("\x1b[2m{}\x1b[0m", line.trim_end());
 }
 
-fn print_error(error: String) {
-    println!("\x1b[1;31merror:\x1b[0m {error}");
+// where the history lives between sessions, if we can find a home for it
+fn history_file() -> Option<String> {
+    std::env::var("HOME")
+        .ok()
+        .map(|home| format!("{home}/.jsl_history"))
 }
 
-fn main() {
-    let mut args: Vec<String> = std::env::args().collect();
-    args.remove(0);
-    if args.len() < 1 {
-        print_error("expected jsl file as argument".into());
-        return;
+fn repl(tree_walk: bool) -> ExitCode {
+    let mut editor = match DefaultEditor::new() {
+        Ok(editor) => editor,
+        Err(e) => {
+            print_error(&JSLError::new(format!("could not start repl: {e}")), None);
+            return ExitCode::FAILURE;
+        }
+    };
+    let history = history_file();
+    if let Some(path) = &history {
+        let _ = editor.load_history(path); // no history yet is fine
+    }
+    // the stack and bindings survive across lines, that's the whole point
+    let mut interpreter = new_interpreter(tree_walk, &[]);
+    let mut code = String::new();
+    let mut status = ExitCode::SUCCESS;
+    // everything typed so far, so errors can point into earlier lines
+    let mut session = String::new();
+    let mut session_lines = 0;
+    loop {
+        let prompt = if code.is_empty() {
+            "jsl› "
+        } else {
+            "   … "
+        };
+        match editor.readline(prompt) {
+            Ok(line) => {
+                code.push_str(line.as_str());
+                code.push('\n');
+                // keep reading while a function (or string) is still open
+                if jsl::is_incomplete(code.as_str()) {
+                    continue;
+                }
+                let _ = editor.add_history_entry(code.trim_end());
+                let start = Span {
+                    start: session.len(),
+                    end: session.len(),
+                    line: session_lines + 1,
+                    col: 1,
+                };
+                session.push_str(code.as_str());
+                session_lines += code.lines().count();
+                let res = interpreter.eval_at(code.as_str(), start);
+                code.clear();
+                match res.err() {
+                    Some(JSLError {
+                        exit: Some(code), ..
+                    }) => {
+                        status = ExitCode::from(code);
+                        break;
+                    }
+                    Some(err) => print_error(&err.in_file("<repl>"), Some(session.as_str())),
+                    None => (),
+                }
+                print_stack(interpreter.stack());
+            }
+            // ^C throws away whatever was being typed
+            Err(ReadlineError::Interrupted) => code.clear(),
+            // ^D quits
+            Err(ReadlineError::Eof) => break,
+            Err(e) => {
+                print_error(&JSLError::new(format!("could not read line: {e}")), None);
+                status = ExitCode::FAILURE;
+                break;
+            }
+        }
     }
-    let file = args[0].clone();
-    let res = main_helper(file);
-    if let Some(err) = res.err() {
-        print_error(err.msg);
+    if let Some(path) = &history {
+        let _ = editor.save_history(path);
+    }
+    status
+}
+
+fn main() -> ExitCode {
+    let mut args = std::env::args().skip(1).peekable();
+    // run with the old tree walker instead of the vm. flags only count before the script,
+    // everything after it belongs to the script
+    let tree_walk = args.next_if(|a| a == "--tree-walk").is_some();
+    let Some(file) = args.next() else {
+        return repl(tree_walk);
+    };
+    let script_args: Vec<String> = args.collect();
+    let code = match fs::read_to_string(&file) {
+        Ok(code) => code,
+        Err(e) => {
+            print_error(
+                &JSLError::new(format!("could not read file: {e}")).in_file(file),
+                None,
+            );
+            return ExitCode::FAILURE;
+        }
+    };
+    match main_helper(code.as_str(), tree_walk, &script_args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(JSLError {
+            exit: Some(code), ..
+        }) => ExitCode::from(code),
+        Err(err) => {
+            print_error(&err.in_file(file), Some(code.as_str()));
+            ExitCode::FAILURE
+        }
     }
 }
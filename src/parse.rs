@@ -1,11 +1,63 @@
 use super::result::*;
-use std::fmt;
+use std::{collections::BTreeMap, fmt, rc::Rc};
 
-type Reader<'a> = std::iter::Peekable<std::str::Chars<'a>>;
+// keeps track of where we are while reading characters
+#[derive(Clone)]
+struct Reader<'a> {
+    chars: std::iter::Peekable<std::str::CharIndices<'a>>,
+    base: usize, // byte offset of the code inside of whatever it was cut from
+    line: usize,
+    col: usize,
+}
+
+impl Reader<'_> {
+    fn next(&mut self) -> Option<char> {
+        let (_, c) = self.chars.next()?;
+        if c == '\n' {
+            self.line += 1;
+            self.col = 1;
+        } else {
+            self.col += 1;
+        }
+        Some(c)
+    }
+
+    fn next_if(&mut self, f: impl FnOnce(char) -> bool) -> Option<char> {
+        match self.peek() {
+            Some(&c) if f(c) => self.next(),
+            _ => None,
+        }
+    }
+
+    fn peek(&mut self) -> Option<&char> {
+        self.chars.peek().map(|(_, c)| c)
+    }
+
+    fn offset(&mut self) -> usize {
+        let base = self.base;
+        self.chars.peek().map_or(usize::MAX, |(i, _)| base + i)
+    }
+
+    // a zero-width span at the current position
+    fn here(&mut self) -> Span {
+        let start = self.offset();
+        Span {
+            start,
+            end: start,
+            line: self.line,
+            col: self.col,
+        }
+    }
+}
 
 // what were you expecting?
-fn reader_from_string<'a>(s: &'a str) -> Reader<'a> {
-    s.chars().peekable()
+fn reader_from_string<'a>(s: &'a str, start: Span) -> Reader<'a> {
+    Reader {
+        chars: s.char_indices().peekable(),
+        base: start.start,
+        line: start.line,
+        col: start.col,
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -19,6 +71,7 @@ enum TokenCategory {
 pub(crate) struct Token {
     category: TokenCategory,
     content: String,
+    span: Span,
 }
 
 impl fmt::Debug for Token {
@@ -27,40 +80,71 @@ impl fmt::Debug for Token {
     }
 }
 
-const SYMBOLS: &str = "{}∅□.:⭥!”,⤉↗→+-×÷=";
+const SYMBOLS: &str = "{}[]∅□⊡.:⭥⇈⟳⊣⊢⊸⊙↕⌧⊏⊐!?⍣↻¨⌿/\\”,⤉⤒⍰⍎⍕⌗≢⍳⌽↑↓✂⊘⊕⍸∊⊲⊳⇄⇡⇣⌦⊶⊷⤈⤓∋⊤⊥⋈↗⍞⍗⍠⇐⇇⇒⇛∃⌫⊞$⎋→+-×÷%^⫽⊓⊔⌊⌈≈⊺‖±√ℯ⍟○=≠<>≤≥¬∧∨";
 const DIGITS: &str = "0123456789";
+const UNTERMINATED_STRING: &str = "unterminated string";
+
+// reads the rest of the digits of a number, and maybe one decimal point
+fn read_digits(reader: &mut Reader, result: &mut String, allow_dot: bool) {
+    let mut has_read_dot = !allow_dot;
+    while let Some(&d) = reader.peek() {
+        if !(DIGITS.contains(d) || d == '.') {
+            break;
+        }
+        if d == '.' {
+            if has_read_dot {
+                break;
+            }
+            has_read_dot = true;
+        }
+        result.push(d);
+        reader.next();
+    }
+}
 
 // bulk of the logic
 // yes this returns a RESULT OF AN OPTION 😭
-fn read_a_token(reader: &mut Reader) -> JSLResult<Option<Token>> {
-    if let Some(ch) = reader.next() {
+fn read_a_token(reader: &mut Reader, end: usize) -> JSLResult<Option<Token>> {
+    let mut span = reader.here();
+    let token = if let Some(ch) = reader.next() {
         match ch {
             // symbol
-            _ if SYMBOLS.contains(ch) => Ok(Some(Token {
-                category: TokenCategory::Symbol,
-                content: ch.into(),
-            })),
-            _ if DIGITS.contains(ch) => {
-                // number
-                let mut result: String = ch.into();
-                let mut has_read_dot = false;
-                while let Some(&d) = reader.clone().peek() {
-                    if !(DIGITS.contains(d) || d == '.') {
-                        break;
+            _ if SYMBOLS.contains(ch) => Some((TokenCategory::Symbol, ch.into())),
+            _ if DIGITS.contains(ch) || ch == '¯' || ch == '∞' => {
+                // number, the content ends up as something rust knows how to parse
+                let mut result = String::new();
+                let mut first = ch;
+                if ch == '¯' {
+                    // high minus, for negative literals (- is subtract)
+                    result.push('-');
+                    match reader.next() {
+                        Some(d) if DIGITS.contains(d) || d == '∞' => first = d,
+                        _ => {
+                            span.end = span.start + ch.len_utf8();
+                            return Err(JSLError::new("expected a number after ¯").at(span));
+                        }
                     }
-                    if d == '.' {
-                        if has_read_dot {
-                            break;
+                }
+                if first == '∞' {
+                    result.push_str("inf");
+                } else {
+                    result.push(first);
+                    read_digits(reader, &mut result, true);
+                    // exponent, only if there's actually a number after the e
+                    let mut ahead = reader.clone();
+                    if ahead.next() == Some('e') {
+                        let sign = ahead.next_if(|c| c == '¯' || c == '-');
+                        if ahead.peek().is_some_and(|&d| DIGITS.contains(d)) {
+                            result.push('e');
+                            if sign.is_some() {
+                                result.push('-');
+                            }
+                            *reader = ahead;
+                            read_digits(reader, &mut result, false);
                         }
-                        has_read_dot = true;
                     }
-                    result.push(d);
-                    reader.next();
                 }
-                Ok(Some(Token {
-                    category: TokenCategory::Number,
-                    content: result,
-                }))
+                Some((TokenCategory::Number, result))
             }
             '"' => {
                 // string
@@ -83,32 +167,32 @@ fn read_a_token(reader: &mut Reader) -> JSLResult<Option<Token>> {
                     }
                 }
                 if finished {
-                    Ok(Some(Token {
-                        category: TokenCategory::String,
-                        content: result,
-                    }))
+                    Some((TokenCategory::String, result))
                 } else {
-                    Err(JSLError {
-                        msg: "unterminated string".into(),
-                    })
+                    // just point at the opening quote, the rest is the whole file
+                    span.end = span.start + 1;
+                    return Err(JSLError::new(UNTERMINATED_STRING).at(span));
                 }
             }
             _ if ch.is_alphabetic() => {
                 // identifiers
                 let mut result: String = ch.into();
-                while let Some(&l) = reader.clone().peek() {
-                    if !l.is_alphabetic() {
+                while let Some(&l) = reader.peek() {
+                    // ℯ is a letter too, but it's taken
+                    if !l.is_alphabetic() || SYMBOLS.contains(l) {
                         break;
                     }
                     result.push(l);
                     reader.next();
                 }
-                Ok(Some(Token {
-                    category: TokenCategory::Identifier,
-                    content: result,
-                }))
+                if result == "NaN" {
+                    // the one identifier that isn't
+                    Some((TokenCategory::Number, result))
+                } else {
+                    Some((TokenCategory::Identifier, result))
+                }
             }
-            _ if ch.is_whitespace() => Ok(None),
+            _ if ch.is_whitespace() => None,
             '#' => {
                 // comments
                 while let Some(i) = reader.next() {
@@ -116,24 +200,41 @@ fn read_a_token(reader: &mut Reader) -> JSLResult<Option<Token>> {
                         break;
                     }
                 }
-                Ok(None)
+                None
             }
-            i => Ok(Some(Token {
-                category: TokenCategory::Identifier,
-                content: i.into(),
-            })), // symbol identifiers
+            i => Some((TokenCategory::Identifier, i.into())), // symbol identifiers
         }
     } else {
-        Ok(None)
-    }
+        None
+    };
+    span.end = reader.offset().min(end);
+    Ok(token.map(|(category, content)| Token {
+        category,
+        content,
+        span,
+    }))
 }
 
 pub fn tokenize(code: &str) -> JSLResult<Vec<Token>> {
-    let mut reader = reader_from_string(code);
+    tokenize_at(
+        code,
+        Span {
+            start: 0,
+            end: 0,
+            line: 1,
+            col: 1,
+        },
+    )
+}
+
+/// tokenizes code that starts at `start` inside of some bigger source (the repl uses this)
+pub fn tokenize_at(code: &str, start: Span) -> JSLResult<Vec<Token>> {
+    let mut reader = reader_from_string(code, start);
+    let end = start.start + code.len();
     let mut result: Vec<Token> = vec![];
-    while reader.clone().peek().is_some() {
+    while reader.peek().is_some() {
         // read tokens 'till there ain't any
-        if let Some(token) = read_a_token(&mut reader)? {
+        if let Some(token) = read_a_token(&mut reader, end)? {
             result.push(token);
         }
     }
@@ -148,6 +249,11 @@ pub(crate) enum Statement {
     Binding(String),
     Identifier(String),
     Literal(Value), // functions actually make this have a circular import, lol
+    // not a literal: every time it runs it captures whatever scope it's in
+    Function(Rc<Function>),
+    // [ ], which runs its body on a stack of its own and makes a list out of what's left. lists
+    // with nothing but literals in them become literals themselves
+    List(Rc<Function>),
     Primitive(Primitive),
 }
 
@@ -157,114 +263,319 @@ impl fmt::Debug for Statement {
             Statement::Binding(i) => write!(f, "→{i}"),
             Statement::Identifier(i) => write!(f, "{i}"),
             Statement::Literal(v) => write!(f, "{v}"),
+            Statement::Function(func) => write!(f, "{{ {:?} }}", func.body),
+            Statement::List(body) => write!(f, "[ {:?} ]", body.body),
             Statement::Primitive(p) => write!(f, "{p:?}"),
         }
     }
 }
 
-pub(crate) type AST = Vec<Statement>;
+// a statement, and where it came from
+#[derive(Clone)]
+pub(crate) struct Node {
+    pub(crate) statement: Statement,
+    pub(crate) span: Span,
+}
+
+impl fmt::Debug for Node {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.statement)
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+pub(crate) type AST = Vec<Node>;
 
 type TokenReader<'a> = std::iter::Peekable<std::slice::Iter<'a, Token>>;
 
-fn token_reader<'a>(tokens: &'a Vec<Token>) -> TokenReader<'a> {
+fn token_reader<'a>(tokens: &'a [Token]) -> TokenReader<'a> {
     tokens.iter().peekable()
 }
 
 enum ParserContext {
     Global,
-    Function,
+    Function(Span), // where the { is
+    List(Span),     // where the [ is
+}
+
+// the span of string[from..to], where string is the source text that starts at span
+fn sub_span(string: &str, span: Span, from: usize, to: usize) -> Span {
+    let before = &string[..from];
+    let (line, col) = match before.rfind('\n') {
+        Some(i) => (
+            span.line + before.matches('\n').count(),
+            before[i + 1..].chars().count() + 1,
+        ),
+        None => (span.line, span.col + before.chars().count()),
+    };
+    Span {
+        start: span.start + from,
+        end: span.start + to,
+        line,
+        col,
+    }
+}
+
+// reads up to max hex digits for \x and \u, also returns where it stopped
+fn read_hex(
+    iter: &mut std::iter::Peekable<std::str::CharIndices>,
+    max: usize,
+    len: usize,
+) -> (String, usize) {
+    let mut digits = String::new();
+    while digits.len() < max {
+        match iter.next_if(|&(_, d)| d.is_ascii_hexdigit()) {
+            Some((_, d)) => digits.push(d),
+            None => break,
+        }
+    }
+    (digits, iter.peek().map_or(len, |&(i, _)| i))
 }
 
 // a better name for this would be parse_string
-fn handle_escapes(string: &str) -> JSLResult<String> {
-    let mut iter = string.chars();
+fn handle_escapes(string: &str, span: Span) -> JSLResult<String> {
+    let mut iter = string.char_indices().peekable();
     let mut result = String::new();
-    while let Some(c) = iter.next() {
+    while let Some((start, c)) = iter.next() {
         match c {
             '"' => (), // do nothing, it's the outer quote
             // the real hassle
-            '\\' => result.push(match iter.next().unwrap() {
-                // unwrap will never fail --------⬏
-                'r' => '\r', // goofy ahh windows
-                'n' => '\n', // newline
-                't' => '\t', // tab
-                '"' => '"',  // quote
-                // invalid escape!
-                invalid => {
-                    return Err(JSLError {
-                        msg: format!("invalid escape sequence: \\{invalid}"),
-                    });
-                }
-            }),
+            '\\' => {
+                // unwrap will never fail, the tokenizer makes sure of that
+                let (_, e) = iter.next().unwrap();
+                let escaped = match e {
+                    'r' => '\r',  // goofy ahh windows
+                    'n' => '\n',  // newline
+                    't' => '\t',  // tab
+                    '"' => '"',   // quote
+                    '\\' => '\\', // backslash
+                    '0' => '\0',  // null
+                    // \xHH, always two digits
+                    'x' => match read_hex(&mut iter, 2, string.len()) {
+                        (digits, _) if digits.len() == 2 => {
+                            // two hex digits always make a valid char
+                            char::from_u32(u32::from_str_radix(&digits, 16).unwrap()).unwrap()
+                        }
+                        (_, end) => {
+                            return Err(JSLError::new(
+                                "invalid escape sequence: \\x needs exactly two hex digits",
+                            )
+                            .at(sub_span(string, span, start, end)));
+                        }
+                    },
+                    // \u{XXXX}, any unicode scalar value
+                    'u' => {
+                        let braced = iter.next_if(|&(_, b)| b == '{').is_some();
+                        let (digits, end) = read_hex(&mut iter, 6, string.len());
+                        let closed = braced && iter.next_if(|&(_, b)| b == '}').is_some();
+                        let end = if closed { end + 1 } else { end };
+                        let err_span = sub_span(string, span, start, end);
+                        if !closed || digits.is_empty() {
+                            return Err(JSLError::new(
+                                "invalid escape sequence: expected \\u{…} with 1 to 6 hex digits",
+                            )
+                            .at(err_span));
+                        }
+                        let code = u32::from_str_radix(&digits, 16).unwrap();
+                        match char::from_u32(code) {
+                            Some(c) => c,
+                            None => {
+                                return Err(JSLError::new(format!(
+                                    "invalid escape sequence: {code:X} is not a unicode character",
+                                ))
+                                .at(err_span));
+                            }
+                        }
+                    }
+                    // invalid escape!
+                    invalid => {
+                        let end = start + 1 + invalid.len_utf8();
+                        return Err(
+                            JSLError::new(format!("invalid escape sequence: \\{invalid}"))
+                                .at(sub_span(string, span, start, end)),
+                        );
+                    }
+                };
+                result.push(escaped);
+            }
             _ => result.push(c), // just push it
         }
     }
     Ok(result)
 }
 
-fn parse_helper(reader: &mut TokenReader, context: ParserContext) -> JSLResult<AST> {
+// also returns the span of the last token it read, so functions know where their } is
+fn parse_helper(reader: &mut TokenReader, context: ParserContext) -> JSLResult<(AST, Span)> {
     let mut tree: AST = vec![];
+    let mut last_span = match context {
+        ParserContext::Function(span) | ParserContext::List(span) => span,
+        ParserContext::Global => reader.peek().map_or(
+            Span {
+                start: 0,
+                end: 0,
+                line: 1,
+                col: 1,
+            },
+            |t| t.span,
+        ),
+    };
     while let Some(token) = reader.next() {
+        let span = token.span;
+        last_span = span;
         // very happy tuple destructuring
-        match (token.category.clone(), token.content.clone().as_str()) {
+        let statement = match (token.category.clone(), token.content.clone().as_str()) {
             (TokenCategory::Number, number) => {
                 // numbers: unwrap NEVER fails ---------------------------⬎
-                tree.push(Statement::Literal(Value::Number(number.parse().unwrap())))
+                Statement::Literal(Value::Number(number.parse().unwrap()))
             }
             // identifiers
-            (TokenCategory::Identifier, ident) => tree.push(Statement::Identifier(ident.into())),
+            (TokenCategory::Identifier, ident) => Statement::Identifier(ident.into()),
             // null
-            (TokenCategory::Symbol, "∅") => tree.push(Statement::Literal(Value::Null)),
+            (TokenCategory::Symbol, "∅") => Statement::Literal(Value::Null),
             // empty list
-            (TokenCategory::Symbol, "□") => tree.push(Statement::Literal(Value::List(vec![]))),
+            (TokenCategory::Symbol, "□") => Statement::Literal(Value::list(vec![])),
+            // empty map
+            (TokenCategory::Symbol, "⊡") => Statement::Literal(Value::map(BTreeMap::new())),
             // binding arrow
             (TokenCategory::Symbol, "→") => {
                 if let Some(Token {
                     category: TokenCategory::Identifier,
                     content: ident,
+                    span: ident_span,
                 }) = reader.next()
                 {
-                    tree.push(Statement::Binding(ident.to_string()))
-                } else {
-                    return Err(JSLError {
-                        msg: "expected identifier after →".into(),
+                    tree.push(Node {
+                        statement: Statement::Binding(ident.to_string()),
+                        span: Span {
+                            end: ident_span.end,
+                            ..span
+                        },
                     });
+                    last_span = *ident_span;
+                    continue;
+                } else {
+                    return Err(JSLError::new("expected identifier after →").at(span));
                 }
             }
             // open function
-            (TokenCategory::Symbol, "{") => tree.push(Statement::Literal(Value::Function(
+            (TokenCategory::Symbol, "{") => {
                 // first-class functions 😭
-                parse_helper(reader, ParserContext::Function)?,
-            ))),
+                let (body, close) = parse_helper(reader, ParserContext::Function(span))?;
+                let span = Span {
+                    end: close.end,
+                    ..span
+                };
+                tree.push(Node {
+                    statement: Statement::Function(Rc::new(Function::new(body, Some(span)))),
+                    span,
+                });
+                last_span = close;
+                continue;
+            }
             // close function
             (TokenCategory::Symbol, "}") => match context {
-                ParserContext::Function => return Ok(tree),
+                ParserContext::Function(_) => return Ok((tree, span)),
                 _ => {
-                    return Err(JSLError {
-                        msg: "unexpected }".into(),
+                    return Err(JSLError::new("unexpected }").at(span));
+                }
+            },
+            // open list
+            (TokenCategory::Symbol, "[") => {
+                let (body, close) = parse_helper(reader, ParserContext::List(span))?;
+                let statement = if body
+                    .iter()
+                    .all(|node| matches!(node.statement, Statement::Literal(_)))
+                {
+                    // nothing to run, so it can be made right now
+                    let items = body.into_iter().map(|node| match node.statement {
+                        Statement::Literal(v) => v,
+                        _ => unreachable!(),
                     });
+                    Statement::Literal(Value::list(items.collect()))
+                } else {
+                    Statement::List(Rc::new(Function::new(body, None)))
+                };
+                tree.push(Node {
+                    statement,
+                    span: Span {
+                        end: close.end,
+                        ..span
+                    },
+                });
+                last_span = close;
+                continue;
+            }
+            // close list
+            (TokenCategory::Symbol, "]") => match context {
+                ParserContext::List(_) => return Ok((tree, span)),
+                _ => {
+                    return Err(JSLError::new("unexpected ]").at(span));
                 }
             },
             // primitives
-            (TokenCategory::Symbol, prim) => tree.push(Statement::Primitive(Primitive::from_char(
+            (TokenCategory::Symbol, prim) => Statement::Primitive(Primitive::from_char(
                 prim.chars().next().unwrap(), // unwrap will NEVER EVER fail
-            ))),
+            )),
             // strings
             (TokenCategory::String, string) => {
-                tree.push(Statement::Literal(Value::String(handle_escapes(string)?)))
+                Statement::Literal(Value::string(handle_escapes(string, span)?))
             }
-        }
+        };
+        tree.push(Node { statement, span });
     }
     // can only finish parsing when on a global context:
     match context {
-        ParserContext::Global => Ok(tree),
-        ParserContext::Function => Err(JSLError {
-            msg: "expected } before eof".into(),
-        }),
+        ParserContext::Global => Ok((tree, last_span)),
+        ParserContext::Function(open) => {
+            Err(JSLError::new("expected } before eof, this { is never closed").at(open))
+        }
+        ParserContext::List(open) => {
+            Err(JSLError::new("expected ] before eof, this [ is never closed").at(open))
+        }
     }
 }
 
 pub(crate) fn parse(tokens: Vec<Token>) -> JSLResult<AST> {
     let mut reader = token_reader(&tokens);
-    parse_helper(&mut reader, ParserContext::Global)
+    parse_helper(&mut reader, ParserContext::Global).map(|(tree, _)| tree)
+}
+
+// used by the repl to decide whether to keep reading lines
+pub(crate) fn is_incomplete(code: &str) -> bool {
+    match tokenize(code) {
+        Ok(tokens) => {
+            let mut depth = 0isize;
+            for token in tokens.iter() {
+                match (&token.category, token.content.as_str()) {
+                    (TokenCategory::Symbol, "{" | "[") => depth += 1,
+                    (TokenCategory::Symbol, "}" | "]") => depth -= 1,
+                    _ => (),
+                }
+            }
+            depth > 0
+        }
+        // strings can go over multiple lines, anything else is just broken
+        Err(e) => e.msg == UNTERMINATED_STRING,
+    }
+}
+
+/// reads a number the same way number literals are read, so anything `↗` prints can come back.
+/// a regular `-` in front works too, since people are going to type that
+pub(crate) fn parse_number(text: &str) -> Option<f64> {
+    let text = text.trim();
+    let text = match text.strip_prefix('-') {
+        Some(rest) => format!("¯{rest}"),
+        None => text.into(),
+    };
+    // the tokenizer skips comments, so make sure the number really is all there is
+    match tokenize(&text).ok()?.as_slice() {
+        [
+            Token {
+                category: TokenCategory::Number,
+                content,
+                span,
+            },
+        ] if span.start == 0 && span.end == text.len() => content.parse().ok(),
+        _ => None,
+    }
 }
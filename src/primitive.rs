@@ -1,4 +1,4 @@
-#[derive(Clone, Debug)]
+#[derive(Clone, Copy, Debug)]
 pub(crate) enum Primitive {
     Pop,
     Duplicate,
@@ -7,12 +7,93 @@ pub(crate) enum Primitive {
     Join,
     Pair,
     Index,
+    StrictIndex,
     Print,
+    ReadLine,
+    ReadAll,
+    ReadChar,
+    ReadFile,
+    ReadLines,
+    WriteFile,
+    AppendFile,
+    Exists,
+    Delete,
+    ListDir,
+    EnvVar,
+    Exit,
     Add,
     Subtract,
     Multiply,
     Divide,
+    Modulo,
+    Power,
+    IntegerDivide,
+    Min,
+    Max,
+    Floor,
+    Ceil,
+    Round,
+    Truncate,
+    Abs,
+    Sign,
+    Sqrt,
+    Exp,
+    Log,
+    Trig,
     Equals,
+    NotEquals,
+    Less,
+    Greater,
+    LessEqual,
+    GreaterEqual,
+    Not,
+    And,
+    Or,
+    If,
+    Repeat,
+    While,
+    Map,
+    Filter,
+    Fold,
+    Scan,
+    Over,
+    Rotate,
+    Nip,
+    Tuck,
+    Dip,
+    Keep,
+    Depth,
+    Clear,
+    Stack,
+    Unstack,
+    TypeOf,
+    ParseNumber,
+    ToString,
+    Repr,
+    Length,
+    Range,
+    Reverse,
+    Take,
+    Drop,
+    Slice,
+    Split,
+    JoinWith,
+    IndexOf,
+    Contains,
+    StartsWith,
+    EndsWith,
+    Replace,
+    Upper,
+    Lower,
+    Trim,
+    CodePoint,
+    Character,
+    Insert,
+    Remove,
+    HasKey,
+    Keys,
+    Values,
+    Entries,
 }
 
 impl Primitive {
@@ -21,16 +102,97 @@ impl Primitive {
             '.' => Primitive::Pop,
             ':' => Primitive::Duplicate,
             '⭥' => Primitive::Flip,
+            '⇈' => Primitive::Over,
+            '⟳' => Primitive::Rotate,
+            '⊣' => Primitive::Nip,
+            '⊢' => Primitive::Tuck,
+            '⊸' => Primitive::Dip,
+            '⊙' => Primitive::Keep,
+            '↕' => Primitive::Depth,
+            '⌧' => Primitive::Clear,
+            '⊏' => Primitive::Stack,
+            '⊐' => Primitive::Unstack,
             '!' => Primitive::Call,
             '”' => Primitive::Join,
             ',' => Primitive::Pair,
             '⤉' => Primitive::Index,
+            '⤒' => Primitive::StrictIndex,
+            '⍰' => Primitive::TypeOf,
+            '⍎' => Primitive::ParseNumber,
+            '⍕' => Primitive::ToString,
+            '⌗' => Primitive::Repr,
+            '≢' => Primitive::Length,
+            '⍳' => Primitive::Range,
+            '⌽' => Primitive::Reverse,
+            '↑' => Primitive::Take,
+            '↓' => Primitive::Drop,
+            '✂' => Primitive::Slice,
+            '⊘' => Primitive::Split,
+            '⊕' => Primitive::JoinWith,
+            '⍸' => Primitive::IndexOf,
+            '∊' => Primitive::Contains,
+            '⊲' => Primitive::StartsWith,
+            '⊳' => Primitive::EndsWith,
+            '⇄' => Primitive::Replace,
+            '⇡' => Primitive::Upper,
+            '⇣' => Primitive::Lower,
+            '⌦' => Primitive::Trim,
+            '⊶' => Primitive::CodePoint,
+            '⊷' => Primitive::Character,
+            '⤈' => Primitive::Insert,
+            '⤓' => Primitive::Remove,
+            '∋' => Primitive::HasKey,
+            '⊤' => Primitive::Keys,
+            '⊥' => Primitive::Values,
+            '⋈' => Primitive::Entries,
             '↗' => Primitive::Print,
+            '⍞' => Primitive::ReadLine,
+            '⍗' => Primitive::ReadAll,
+            '⍠' => Primitive::ReadChar,
+            '⇐' => Primitive::ReadFile,
+            '⇇' => Primitive::ReadLines,
+            '⇒' => Primitive::WriteFile,
+            '⇛' => Primitive::AppendFile,
+            '∃' => Primitive::Exists,
+            '⌫' => Primitive::Delete,
+            '⊞' => Primitive::ListDir,
+            '$' => Primitive::EnvVar,
+            '⎋' => Primitive::Exit,
             '+' => Primitive::Add,
             '-' => Primitive::Subtract,
             '×' => Primitive::Multiply,
             '÷' => Primitive::Divide,
+            '%' => Primitive::Modulo,
+            '^' => Primitive::Power,
+            '⫽' => Primitive::IntegerDivide,
+            '⊓' => Primitive::Min,
+            '⊔' => Primitive::Max,
+            '⌊' => Primitive::Floor,
+            '⌈' => Primitive::Ceil,
+            '≈' => Primitive::Round,
+            '⊺' => Primitive::Truncate,
+            '‖' => Primitive::Abs,
+            '±' => Primitive::Sign,
+            '√' => Primitive::Sqrt,
+            'ℯ' => Primitive::Exp,
+            '⍟' => Primitive::Log,
+            '○' => Primitive::Trig,
             '=' => Primitive::Equals,
+            '≠' => Primitive::NotEquals,
+            '<' => Primitive::Less,
+            '>' => Primitive::Greater,
+            '≤' => Primitive::LessEqual,
+            '≥' => Primitive::GreaterEqual,
+            '¬' => Primitive::Not,
+            '∧' => Primitive::And,
+            '∨' => Primitive::Or,
+            '?' => Primitive::If,
+            '⍣' => Primitive::Repeat,
+            '↻' => Primitive::While,
+            '¨' => Primitive::Map,
+            '⌿' => Primitive::Filter,
+            '/' => Primitive::Fold,
+            '\\' => Primitive::Scan,
             _ => unreachable!(),
         }
     }
@@ -1,7 +1,194 @@
+use std::fmt::{self, Write};
+
+/// where something is in the source code
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct Span {
+    /// byte offset of the first character
+    pub start: usize,
+    /// byte offset just past the last character
+    pub end: usize,
+    /// 1-based line of the first character
+    pub line: usize,
+    /// 1-based column (in characters) of the first character
+    pub col: usize,
+}
+
+/// one step of the way out of a failed program
+#[derive(Debug, Clone)]
+#[non_exhaustive]
+pub enum TraceFrame {
+    /// a function that was called with `!`
+    Call {
+        /// the identifier the function was read from, if it was called right after one
+        name: Option<String>,
+        /// where the function literal is, if it was written as one
+        defined_at: Option<Span>,
+        /// where the `!` is
+        call_site: Span,
+    },
+    /// tail calls don't keep their frames around, so we can only say how many there were
+    Elided(usize),
+}
+
+/// something that went wrong while tokenizing, parsing or running code
 #[derive(Debug)]
+#[non_exhaustive]
 pub struct JSLError {
+    /// what went wrong, for humans
     pub msg: String,
+    /// where in the source it went wrong, if we know
+    pub span: Option<Span>,
+    /// what file the source came from, if it came from one
+    pub file: Option<String>,
+    /// innermost first
+    pub trace: Vec<TraceFrame>,
+    /// set when the program asked to exit, which isn't really an error but unwinds just like one
+    pub exit: Option<u8>,
 }
 
+impl JSLError {
+    /// an error that doesn't know where it happened (yet)
+    pub fn new(msg: impl Into<String>) -> Self {
+        JSLError {
+            msg: msg.into(),
+            span: None,
+            file: None,
+            trace: vec![],
+            exit: None,
+        }
+    }
+
+    /// what `⎋` "errors" with, carrying the status the program wants to exit with
+    pub fn exit(code: u8) -> Self {
+        JSLError {
+            exit: Some(code),
+            ..JSLError::new(format!("exited with status {code}"))
+        }
+    }
+
+    /// attaches a span, unless the error already knows a more precise one
+    pub fn at(mut self, span: Span) -> Self {
+        self.span.get_or_insert(span);
+        self
+    }
+
+    /// records a frame the error has propagated out of
+    pub fn traced(mut self, frame: TraceFrame) -> Self {
+        self.trace.push(frame);
+        self
+    }
+
+    /// attaches a file name, unless the error already has one
+    pub fn in_file(mut self, file: impl Into<String>) -> Self {
+        self.file.get_or_insert(file.into());
+        self
+    }
+
+    /// everything there is to say about the error, the way the `jsl` command prints it: the
+    /// message, the line it happened on with the offending bit underlined (if you hand over the
+    /// `source` it came from), and the trace. `color` adds terminal colors
+    pub fn report(&self, source: Option<&str>, color: bool) -> String {
+        let paint = |style: &str, text: &str| {
+            if color {
+                format!("\x1b[{style}m{text}\x1b[0m")
+            } else {
+                text.to_string()
+            }
+        };
+        // writing to a string can't fail, so none of the writeln!s are checked
+        let mut out = String::new();
+        let _ = writeln!(out, "{} {}", paint("1;31", "error:"), self.msg);
+        let file = self.file.as_deref().unwrap_or("<unknown>");
+        let Some(span) = self.span else {
+            if let Some(file) = &self.file {
+                let _ = writeln!(out, "  {} {file}", paint("1;34", "-->"));
+            }
+            return out;
+        };
+        let _ = writeln!(
+            out,
+            "  {} {file}:{}:{}",
+            paint("1;34", "-->"),
+            span.line,
+            span.col
+        );
+
+        // rustc-style: show the line and underline the offending bit
+        if let Some(source) = source.filter(|s| span.start <= s.len()) {
+            let line_start = source[..span.start].rfind('\n').map_or(0, |i| i + 1);
+            let line_end = source[span.start..]
+                .find('\n')
+                .map_or(source.len(), |i| span.start + i);
+            let line_no = span.line.to_string();
+            let gutter = " ".repeat(line_no.len());
+            let before = source[line_start..span.start].chars().count();
+            let width = source[span.start..span.end.clamp(span.start, line_end)]
+                .chars()
+                .count()
+                .max(1);
+            let _ = writeln!(out, "{gutter} {}", paint("1;34", "|"));
+            let _ = writeln!(
+                out,
+                "{} {}",
+                paint("1;34", &format!("{line_no} |")),
+                &source[line_start..line_end]
+            );
+            let _ = writeln!(
+                out,
+                "{gutter} {} {}{}",
+                paint("1;34", "|"),
+                " ".repeat(before),
+                paint("1;31", &"^".repeat(width))
+            );
+        }
+
+        if !self.trace.is_empty() {
+            let _ = writeln!(out, "{} (innermost first):", paint("1", "trace"));
+        }
+        for frame in &self.trace {
+            let _ = match frame {
+                TraceFrame::Call {
+                    name,
+                    defined_at,
+                    call_site,
+                } => {
+                    let what = match (name, defined_at) {
+                        (Some(name), _) => name.clone(),
+                        (None, Some(at)) => format!("function literal at {}:{}", at.line, at.col),
+                        (None, None) => "composed function".into(),
+                    };
+                    writeln!(
+                        out,
+                        "  in {what}, called at {file}:{}:{}",
+                        call_site.line, call_site.col
+                    )
+                }
+                TraceFrame::Elided(n) => writeln!(
+                    out,
+                    "  … {n} frame{} elided by tail calls",
+                    if *n == 1 { "" } else { "s" }
+                ),
+            };
+        }
+        out
+    }
+}
+
+/// just the message and where it happened, like `cannot add number and string at main.jsl:1:3`.
+/// [`JSLError::report`] has the whole story
+impl fmt::Display for JSLError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.msg)?;
+        match (&self.file, self.span) {
+            (Some(file), Some(span)) => write!(f, " at {file}:{}:{}", span.line, span.col),
+            (None, Some(span)) => write!(f, " at {}:{}", span.line, span.col),
+            (Some(file), None) => write!(f, " in {file}"),
+            (None, None) => Ok(()),
+        }
+    }
+}
+
+impl std::error::Error for JSLError {}
+
 /// yada yada yada result type
 pub type JSLResult<T> = Result<T, JSLError>;
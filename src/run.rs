@@ -1,243 +1,1386 @@
-use super::{parse::*, primitive::*, result::*, value::*};
-use std::{collections::HashMap, io::Write, io::stdout, iter::once};
+use super::{io::Io, parse::*, primitive::*, result::*, scope::*, value::*, vm::Vm};
+use std::{cmp::Ordering, fs, io::Write, iter::once, rc::Rc};
 
 pub(crate) fn gen_ast_from_code(code: &str) -> JSLResult<AST> {
     tokenize(code).and_then(parse) // quick little shortcut
 }
 
+/// same thing, but for code that starts at `start` inside of a bigger source
+pub(crate) fn gen_ast_from_code_at(code: &str, start: Span) -> JSLResult<AST> {
+    tokenize_at(code, start).and_then(parse)
+}
+
+/// what runs the code: the bytecode vm, or the original tree walker (handy for checking that
+/// the two agree)
+pub(crate) enum Engine {
+    Vm(Vm),
+    TreeWalker { globals: Env, io: Io },
+}
+
+impl Engine {
+    pub(crate) fn new(tree_walk: bool) -> Self {
+        if tree_walk {
+            Engine::TreeWalker {
+                globals: Scope::root(),
+                io: Io::default(),
+            }
+        } else {
+            Engine::Vm(Vm::default())
+        }
+    }
+
+    pub(crate) fn globals(&self) -> Env {
+        match self {
+            Engine::Vm(vm) => vm.globals.clone(),
+            Engine::TreeWalker { globals, .. } => globals.clone(),
+        }
+    }
+
+    pub(crate) fn io(&mut self) -> &mut Io {
+        match self {
+            Engine::Vm(vm) => &mut vm.io,
+            Engine::TreeWalker { io, .. } => io,
+        }
+    }
+
+    pub(crate) fn run(&mut self, ast: AST, stack: &mut Vec<Value>) -> JSLResult<()> {
+        match self {
+            Engine::Vm(vm) => vm.run(ast, stack),
+            Engine::TreeWalker { globals, io } => run_ast(
+                Rc::new(Function::new(ast, None)),
+                stack,
+                globals.clone(),
+                io,
+            ),
+        }
+    }
+}
+
+/// whatever is running the code, so that primitives can call functions (and print) too
+pub(crate) trait Caller {
+    fn call(&mut self, f: Value, stack: &mut Vec<Value>) -> JSLResult<()>;
+    fn io(&mut self) -> &mut Io;
+}
+
+// how the tree walker calls functions for primitives, `site` being where the primitive is
+struct Walker<'a> {
+    site: Span,
+    io: &'a mut Io,
+}
+
+impl Caller for Walker<'_> {
+    fn call(&mut self, f: Value, stack: &mut Vec<Value>) -> JSLResult<()> {
+        let (func, env) = match f {
+            Value::Function(Closure { func, env }) => (func, env),
+            Value::Native(native) => return native.call(stack, self.site),
+            _ => return Err(JSLError::new("invalid function")),
+        };
+        let frame = TraceFrame::Call {
+            name: None,
+            defined_at: func.span,
+            call_site: self.site,
+        };
+        run_ast(func, stack, Scope::child(&env), self.io).map_err(|e| e.traced(frame))
+    }
+
+    fn io(&mut self) -> &mut Io {
+        self.io
+    }
+}
+
+// out here so the (deeply recursive) tree walker doesn't have to make room for it in every frame
+fn call_native(stack: &mut Vec<Value>, span: Span) -> JSLResult<()> {
+    match stack.pop() {
+        Some(Value::Native(native)) => native.call(stack, span),
+        _ => unreachable!(), // only called when there's one on top
+    }
+}
+
 pub(crate) fn run_ast(
-    ast: AST,
+    func: Rc<Function>,
+    stack: &mut Vec<Value>,
+    env: Env,
+    io: &mut Io,
+) -> JSLResult<()> {
+    let mut elided = 0;
+    run_ast_helper(func, stack, env, io, &mut elided).map_err(|e| {
+        // the error happened after all of the tail calls, so the note goes right after it
+        if elided > 0 {
+            e.traced(TraceFrame::Elided(elided))
+        } else {
+            e
+        }
+    })
+}
+
+// walks the body in place instead of taking it apart, so calls don't have to copy it
+fn run_ast_helper(
+    mut func: Rc<Function>,
     stack: &mut Vec<Value>,
-    vars: &mut HashMap<String, Value>,
+    mut env: Env,
+    io: &mut Io,
+    elided: &mut usize,
 ) -> JSLResult<()> {
-    let mut iter = ast.into_iter().peekable();
-    while let Some(statement) = iter.next() {
-        match statement {
+    let mut ip = 0;
+    while let Some(node) = func.body.get(ip) {
+        ip += 1;
+        let span = node.span;
+        match &node.statement {
             Statement::Binding(id) => {
                 // ok these ones have default values
-                vars.insert(id, stack.pop().unwrap_or(Value::Null));
-            }
-            Statement::Literal(v) => stack.push(v),
-            Statement::Identifier(id) => stack.push(vars.get(&id).unwrap_or(&Value::Null).clone()),
-            // oooh boy!
-            // and yes this handles EVERY PRIMITIVE in ONE FUNCTION so deal with this very long
-            // match statement
-            Statement::Primitive(p) => match p {
-                Primitive::Pop => {
-                    stack.pop();
-                }
-                Primitive::Duplicate => stack.push(stack.last().unwrap_or(&Value::Null).clone()),
-                Primitive::Flip => match (stack.pop(), stack.pop()) {
-                    (Some(x), Some(y)) => {
-                        stack.push(x);
-                        stack.push(y);
-                    }
-                    // do nothing if not enough stack values
-                    (Some(x), _) => stack.push(x),
-                    _ => (),
-                },
-                Primitive::Print => {
-                    print!("{}", stack.pop().unwrap_or(Value::Null));
-                    stdout().flush().or(Err(JSLError {
-                        msg: "could not flush output :(".into(),
-                    }))?; // ← needed because rust is dumb and doesn't flush output
-                }
-                Primitive::Call => match stack.pop().unwrap_or(Value::Null) {
-                    Value::Function(ast) => {
-                        if iter.clone().peek().is_some() {
-                            run_ast(ast, stack, &mut (*vars).clone())?; // same stack reference,
-                        // new vars reference
-                        } else {
-                            iter = ast.into_iter().peekable(); // tail recursion!
-                        }
+                env.set_named(id, stack.pop().unwrap_or(Value::Null));
+            }
+            Statement::Literal(v) => stack.push(v.clone()),
+            Statement::Function(body) => stack.push(Value::Function(Closure {
+                func: body.clone(),
+                env: env.clone(),
+            })),
+            // the body runs in this same scope, just with its own stack
+            Statement::List(body) => {
+                let mut items = vec![];
+                run_ast(body.clone(), &mut items, env.clone(), io)?;
+                stack.push(Value::list(items));
+            }
+            Statement::Identifier(id) => stack.push(env.get_named(id)),
+            // call is the only primitive that needs to know about the rest of the code, and ? is
+            // just picking a function and then calling it
+            &Statement::Primitive(p @ (Primitive::Call | Primitive::If)) => {
+                if let Primitive::If = p {
+                    run_primitive(p, stack, &mut Walker { site: span, io })
+                        .map_err(|e| e.at(span))?;
+                }
+                // rust functions don't have a body to tail call into, they just run
+                if let Some(Value::Native(_)) = stack.last() {
+                    call_native(stack, span)?;
+                    continue;
+                }
+                let Value::Function(Closure {
+                    func: callee,
+                    env: captured,
+                }) = stack.pop().unwrap_or(Value::Null)
+                else {
+                    return Err(JSLError::new("invalid function").at(span));
+                };
+                // every call gets a fresh scope inside the one the function was written in
+                let scope = Scope::child(&captured);
+                if ip < func.body.len() {
+                    let frame = TraceFrame::Call {
+                        name: called_name(&func, ip, p),
+                        defined_at: callee.span,
+                        call_site: span,
+                    };
+                    // same stack reference, new scope
+                    run_ast(callee, stack, scope, io).map_err(|e| e.traced(frame))?;
+                } else {
+                    func = callee; // tail recursion!
+                    ip = 0;
+                    env = scope;
+                    *elided += 1;
+                }
+            }
+            &Statement::Primitive(p) => {
+                run_primitive(p, stack, &mut Walker { site: span, io }).map_err(|e| e.at(span))?
+            }
+        }
+    }
+    Ok(())
+}
+
+// the identifier right before a !, so traces can say what was called. ? picks a function, so
+// there's no telling
+fn called_name(func: &Function, ip: usize, p: Primitive) -> Option<String> {
+    match (p, ip.checked_sub(2).map(|i| &func.body[i].statement)) {
+        (Primitive::Call, Some(Statement::Identifier(id))) => Some(id.clone()),
+        _ => None,
+    }
+}
+
+// a list this long would already take gigabytes
+const MAX_RANGE_LEN: f64 = 1e8;
+
+// oooh boy!
+// and yes this handles EVERY PRIMITIVE in ONE FUNCTION so deal with this very long
+// match statement
+pub(crate) fn run_primitive(
+    p: Primitive,
+    stack: &mut Vec<Value>,
+    caller: &mut dyn Caller,
+) -> JSLResult<()> {
+    match p {
+        Primitive::Pop => {
+            stack.pop();
+        }
+        Primitive::Duplicate => stack.push(stack.last().unwrap_or(&Value::Null).clone()),
+        Primitive::Flip => match (stack.pop(), stack.pop()) {
+            (Some(x), Some(y)) => {
+                stack.push(x);
+                stack.push(y);
+            }
+            // do nothing if not enough stack values
+            (Some(x), _) => stack.push(x),
+            _ => (),
+        },
+        // no flushing here, that happens before reading input and once the code is done running
+        Primitive::Print => caller.io().print(&stack.pop().unwrap_or(Value::Null))?,
+        Primitive::ReadLine => {
+            let line = caller.io().read_line()?;
+            stack.push(line.map_or(Value::Null, Value::string));
+        }
+        Primitive::ReadAll => {
+            let all = caller.io().read_all()?;
+            stack.push(Value::string(all));
+        }
+        Primitive::ReadChar => {
+            let c = caller.io().read_char()?;
+            stack.push(c.map_or(Value::Null, Value::from));
+        }
+        Primitive::EnvVar => match stack.pop() {
+            Some(Value::String(name)) => stack.push(
+                // unset (or not unicode) is just ∅, most programs have a default anyway
+                std::env::var(name.as_str()).map_or(Value::Null, Value::string),
+            ),
+            Some(x) => {
+                return Err(JSLError::new(format!(
+                    "cannot look up an environment variable named by {}",
+                    x.type_str()
+                )));
+            }
+            None => {
+                return Err(JSLError::new(
+                    "not enough values for $ environment variable",
+                ));
+            }
+        },
+        Primitive::Exit => match stack.pop() {
+            Some(Value::Number(n)) if n.fract() == 0.0 && (0.0..=255.0).contains(&n) => {
+                return Err(JSLError::exit(n as u8));
+            }
+            Some(x) => {
+                return Err(JSLError::new(format!(
+                    "⎋ exit expects a status from 0 to 255, not {x:?}"
+                )));
+            }
+            None => return Err(JSLError::new("not enough values for ⎋ exit")),
+        },
+        Primitive::ReadFile | Primitive::ReadLines => {
+            let (symbol, name) = match p {
+                Primitive::ReadFile => ("⇐ read file", "read file"),
+                _ => ("⇇ read lines", "read lines"),
+            };
+            let path = pop_path(stack, symbol, name)?;
+            let text = fs::read_to_string(path.as_str()).map_err(|e| fs_error("read", &path, e))?;
+            stack.push(match p {
+                Primitive::ReadFile => Value::string(text),
+                _ => Value::list(text.lines().map(Value::string).collect()),
+            });
+        }
+        Primitive::WriteFile | Primitive::AppendFile => {
+            let (symbol, name) = match p {
+                Primitive::WriteFile => ("⇒ write file", "write file"),
+                _ => ("⇛ append file", "append file"),
+            };
+            let path = pop_path(stack, symbol, name)?;
+            let text = match stack.pop() {
+                Some(Value::String(s)) => s,
+                Some(x) => {
+                    return Err(JSLError::new(format!(
+                        "cannot write {} to a file",
+                        x.type_str()
+                    )));
+                }
+                None => return Err(JSLError::new(format!("not enough values for {symbol}"))),
+            };
+            let result = match p {
+                Primitive::WriteFile => fs::write(path.as_str(), text.as_str()),
+                _ => fs::OpenOptions::new()
+                    .append(true)
+                    .create(true)
+                    .open(path.as_str())
+                    .and_then(|mut file| file.write_all(text.as_bytes())),
+            };
+            result.map_err(|e| fs_error("write to", &path, e))?;
+        }
+        Primitive::Exists => {
+            let path = pop_path(stack, "∃ exists", "exists")?;
+            let exists = fs::exists(path.as_str()).map_err(|e| fs_error("check", &path, e))?;
+            stack.push(Value::Number(exists as u8 as f64));
+        }
+        Primitive::Delete => {
+            let path = pop_path(stack, "⌫ delete", "delete")?;
+            // directories only get deleted when they're empty, no rm -rf accidents here.
+            // a symlink to a directory is still just a link, so don't follow it
+            let result = match fs::symlink_metadata(path.as_str()) {
+                Ok(meta) if meta.is_dir() => fs::remove_dir(path.as_str()),
+                _ => fs::remove_file(path.as_str()),
+            };
+            result.map_err(|e| fs_error("delete", &path, e))?;
+        }
+        Primitive::ListDir => {
+            let path = pop_path(stack, "⊞ list directory", "list directory")?;
+            let mut names = fs::read_dir(path.as_str())
+                .and_then(|entries| {
+                    entries
+                        .map(|entry| Ok(entry?.file_name().to_string_lossy().into_owned()))
+                        .collect::<std::io::Result<Vec<String>>>()
+                })
+                .map_err(|e| fs_error("list", &path, e))?;
+            // read_dir gives them in whatever order the os feels like
+            names.sort();
+            stack.push(Value::list(names.into_iter().map(Value::string).collect()));
+        }
+        // these reuse the buffer of the top value whenever nothing else is holding on to it
+        Primitive::Join => match (stack.pop(), stack.pop()) {
+            (Some(Value::String(mut a)), Some(Value::String(b))) => {
+                Rc::make_mut(&mut a).push_str(b.as_str());
+                stack.push(Value::String(a));
+            }
+            (Some(Value::String(mut a)), Some(Value::Number(b))) => {
+                Rc::make_mut(&mut a).push_str(&Value::Number(b).to_string());
+                stack.push(Value::String(a));
+            }
+            (Some(Value::Number(a)), Some(Value::String(b))) => {
+                let mut res = Value::Number(a).to_string();
+                res.push_str(b.as_str());
+                stack.push(Value::string(res));
+            }
+            // compose 😈
+            (
+                Some(a @ (Value::Function(_) | Value::Native(_))),
+                Some(b @ (Value::Function(_) | Value::Native(_))),
+            ) => {
+                stack.push(Value::Function(compose(a, b)));
+            }
+            // wrap a value in a list if you want lists to get joined as lists
+            (Some(Value::List(mut a)), Some(Value::List(b))) => {
+                Rc::make_mut(&mut a).extend(b.iter().cloned());
+                stack.push(Value::List(a));
+            }
+            (Some(Value::List(mut a)), Some(b)) => {
+                Rc::make_mut(&mut a).push(b);
+                stack.push(Value::List(a));
+            }
+            (Some(a), Some(Value::List(b))) => {
+                stack.push(Value::list(once(a).chain(b.iter().cloned()).collect()));
+            }
+            (Some(x), Some(y)) => {
+                return Err(JSLError::new(format!(
+                    "cannot join {} and {}",
+                    x.type_str(),
+                    y.type_str()
+                )));
+            }
+            _ => {
+                return Err(JSLError::new("not enough values for ” join"));
+            }
+        },
+        Primitive::Pair => {
+            let a = stack.pop().unwrap_or(Value::Null);
+            let b = stack.pop().unwrap_or(Value::Null);
+            stack.push(Value::list(vec![a, b]));
+        }
+        Primitive::Index | Primitive::StrictIndex => {
+            let strict = matches!(p, Primitive::StrictIndex);
+            let symbol = if strict {
+                "⤒ strict index"
+            } else {
+                "⤉ index"
+            };
+            match (stack.pop() /* index */, stack.pop() /* target */) {
+                (Some(i), Some(target)) => stack.push(index(target, i, strict)?),
+                _ => {
+                    return Err(JSLError::new(format!("not enough values for {symbol}")));
+                }
+            }
+        }
+        Primitive::Add => match (stack.pop(), stack.pop()) {
+            (Some(Value::Number(x)), Some(Value::Number(y))) => stack.push(Value::Number(x + y)),
+            (Some(x), Some(y)) => {
+                let use_join_hint = matches!(
+                    (x.type_str(), y.type_str()),
+                    ("string", "string") | ("list", "list")
+                );
+                return Err(JSLError::new(format!(
+                    "cannot add {} and {}{}",
+                    x.type_str(),
+                    y.type_str(),
+                    if use_join_hint {
+                        ". perhaps you meant to use ” join?"
+                    } else {
+                        ""
                     }
+                )));
+            }
+            _ => {
+                return Err(JSLError::new("not enough values for + add"));
+            }
+        },
+        Primitive::Subtract => match (stack.pop(), stack.pop()) {
+            (Some(Value::Number(x)), Some(Value::Number(y))) => stack.push(Value::Number(y - x)),
+            (Some(x), Some(y)) => {
+                return Err(JSLError::new(format!(
+                    "cannot subtract {} from {}",
+                    x.type_str(),
+                    y.type_str()
+                )));
+            }
+            _ => {
+                return Err(JSLError::new("not enough values for - subtract"));
+            }
+        },
+        Primitive::Multiply => match (stack.pop(), stack.pop()) {
+            (Some(Value::Number(x)), Some(Value::Number(y))) => stack.push(Value::Number(x * y)),
+            (Some(x), Some(y)) => {
+                return Err(JSLError::new(format!(
+                    "cannot multiply {} and {}",
+                    x.type_str(),
+                    y.type_str()
+                )));
+            }
+            _ => {
+                return Err(JSLError::new("not enough values for × multiply"));
+            }
+        },
+        Primitive::Divide => match (stack.pop(), stack.pop()) {
+            (Some(Value::Number(x)), Some(Value::Number(y))) => stack.push(Value::Number(y / x)),
+            (Some(x), Some(y)) => {
+                return Err(JSLError::new(format!(
+                    "cannot divide {} by {}",
+                    y.type_str(),
+                    x.type_str()
+                )));
+            }
+            _ => {
+                return Err(JSLError::new("not enough values for ÷ divide"));
+            }
+        },
+        // y is below x, so `7 2 %` is 7 mod 2
+        Primitive::Modulo => binary_math(stack, "% modulo", "take the modulo of", "by", |y, x| {
+            // floored, so the result has the sign of the divisor
+            y - x * (y / x).floor()
+        })?,
+        Primitive::Power => binary_math(stack, "^ power", "raise", "to", f64::powf)?,
+        Primitive::IntegerDivide => {
+            binary_math(stack, "⫽ integer divide", "divide", "by", |y, x| {
+                (y / x).floor()
+            })?
+        }
+        Primitive::Min => binary_math(stack, "⊓ min", "take the minimum of", "and", f64::min)?,
+        Primitive::Max => binary_math(stack, "⊔ max", "take the maximum of", "and", f64::max)?,
+        Primitive::Floor => unary_math(stack, "⌊ floor", "floor", f64::floor)?,
+        Primitive::Ceil => unary_math(stack, "⌈ ceiling", "take the ceiling of", f64::ceil)?,
+        Primitive::Round => unary_math(stack, "≈ round", "round", f64::round)?,
+        Primitive::Truncate => unary_math(stack, "⊺ truncate", "truncate", f64::trunc)?,
+        Primitive::Abs => unary_math(
+            stack,
+            "‖ absolute value",
+            "take the absolute value of",
+            f64::abs,
+        )?,
+        Primitive::Sign => unary_math(stack, "± sign", "take the sign of", |n| {
+            // f64::signum gives 1 for 0, which isn't what anyone wants
+            if n == 0.0 || n.is_nan() {
+                n
+            } else {
+                n.signum()
+            }
+        })?,
+        Primitive::Sqrt => {
+            unary_math(stack, "√ square root", "take the square root of", f64::sqrt)?
+        }
+        Primitive::Exp => unary_math(stack, "ℯ exponential", "exponentiate", f64::exp)?,
+        Primitive::Log => unary_math(stack, "⍟ logarithm", "take the logarithm of", f64::ln)?,
+        Primitive::Trig => match (stack.pop(), stack.pop()) {
+            (Some(Value::Number(which)), Some(Value::Number(n))) => {
+                let f: fn(f64) -> f64 = match which {
+                    1.0 => f64::sin,
+                    2.0 => f64::cos,
+                    3.0 => f64::tan,
+                    -1.0 => f64::asin,
+                    -2.0 => f64::acos,
+                    -3.0 => f64::atan,
                     _ => {
-                        return Err(JSLError {
-                            msg: "invalid function".into(),
-                        });
+                        return Err(JSLError::new(format!(
+                            "○ trig expects 1, 2, 3, ¯1, ¯2 or ¯3 to pick a function, not {:?}",
+                            Value::Number(which)
+                        )));
                     }
-                },
-                Primitive::Join => match (stack.pop(), stack.pop()) {
-                    (Some(Value::String(a)), Some(Value::String(b))) => {
-                        let mut res = a.clone();
-                        res.push_str(b.as_str());
-                        stack.push(Value::String(res));
-                    }
-                    (Some(Value::String(a)), Some(Value::Number(b))) => {
-                        let mut res = a.clone();
-                        res.push_str(format!("{b}").as_str());
-                        stack.push(Value::String(res));
-                    }
-                    (Some(Value::Number(a)), Some(Value::String(b))) => {
-                        let mut res = format!("{a}");
-                        res.push_str(b.as_str());
-                        stack.push(Value::String(res));
-                    }
-                    // compose 😈
-                    (Some(Value::Function(a)), Some(Value::Function(b))) => {
-                        stack.push(Value::Function(a.into_iter().chain(b).collect()));
-                    }
-                    // wrap a value in a list if you want lists to get joined as lists
-                    (Some(Value::List(a)), Some(Value::List(b))) => {
-                        stack.push(Value::List(a.into_iter().chain(b).collect()));
-                    }
-                    (Some(Value::List(a)), Some(b)) => {
-                        stack.push(Value::List(a.into_iter().chain(once(b)).collect()));
-                    }
-                    (Some(a), Some(Value::List(b))) => {
-                        stack.push(Value::List(once(a).chain(b).collect()));
+                };
+                stack.push(Value::Number(f(n)));
+            }
+            (Some(x), Some(y)) => {
+                return Err(JSLError::new(format!(
+                    "cannot use trig functions on {} and {}",
+                    y.type_str(),
+                    x.type_str()
+                )));
+            }
+            _ => {
+                return Err(JSLError::new("not enough values for ○ trig"));
+            }
+        },
+        Primitive::Equals => match (stack.pop(), stack.pop()) {
+            (Some(x), Some(y)) => stack.push(Value::Number((x == y).into())),
+            _ => {
+                return Err(JSLError::new("not enough values for = equals"));
+            }
+        },
+        Primitive::NotEquals => match (stack.pop(), stack.pop()) {
+            (Some(x), Some(y)) => stack.push(Value::Number((x != y).into())),
+            _ => {
+                return Err(JSLError::new("not enough values for ≠ not equals"));
+            }
+        },
+        Primitive::Less | Primitive::Greater | Primitive::LessEqual | Primitive::GreaterEqual => {
+            let (symbol, name) = match p {
+                Primitive::Less => ('<', "less than"),
+                Primitive::Greater => ('>', "greater than"),
+                Primitive::LessEqual => ('≤', "less or equal"),
+                _ => ('≥', "greater or equal"),
+            };
+            match (stack.pop(), stack.pop()) {
+                (Some(x), Some(y)) => {
+                    // y is below x, so `1 2 <` asks if 1 < 2
+                    let res = match compare(&y, &x)? {
+                        Some(ordering) => match p {
+                            Primitive::Less => ordering.is_lt(),
+                            Primitive::Greater => ordering.is_gt(),
+                            Primitive::LessEqual => ordering.is_le(),
+                            _ => ordering.is_ge(),
+                        },
+                        None => false, // NaN isn't bigger or smaller than anything
+                    };
+                    stack.push(Value::Number(res.into()));
+                }
+                _ => {
+                    return Err(JSLError::new(format!(
+                        "not enough values for {symbol} {name}"
+                    )));
+                }
+            }
+        }
+        Primitive::Not => match stack.pop() {
+            Some(x) => stack.push(Value::Number((!x.is_truthy()).into())),
+            None => return Err(JSLError::new("not enough values for ¬ not")),
+        },
+        Primitive::And => match (stack.pop(), stack.pop()) {
+            (Some(x), Some(y)) => {
+                stack.push(Value::Number((x.is_truthy() && y.is_truthy()).into()))
+            }
+            _ => return Err(JSLError::new("not enough values for ∧ and")),
+        },
+        Primitive::Or => match (stack.pop(), stack.pop()) {
+            (Some(x), Some(y)) => {
+                stack.push(Value::Number((x.is_truthy() || y.is_truthy()).into()))
+            }
+            _ => return Err(JSLError::new("not enough values for ∨ or")),
+        },
+        // only does the picking, whoever runs the code does the calling
+        Primitive::If => match (stack.pop(), stack.pop(), stack.pop()) {
+            (Some(otherwise), Some(then), Some(condition)) => {
+                stack.push(if condition.is_truthy() {
+                    then
+                } else {
+                    otherwise
+                })
+            }
+            _ => return Err(JSLError::new("not enough values for ? if")),
+        },
+        Primitive::Repeat => match (stack.pop(), stack.pop()) {
+            (Some(f @ (Value::Function(_) | Value::Native(_))), Some(Value::Number(n))) => {
+                if n < 0.0 || n.fract() != 0.0 || n.is_infinite() {
+                    return Err(JSLError::new(format!(
+                        "cannot repeat {:?} times",
+                        Value::Number(n)
+                    )));
+                }
+                for _ in 0..n as u64 {
+                    caller.call(f.clone(), stack)?;
+                }
+            }
+            (Some(x), Some(y)) => {
+                return Err(JSLError::new(format!(
+                    "cannot repeat {} {} times",
+                    x.type_str(),
+                    y.type_str()
+                )));
+            }
+            _ => return Err(JSLError::new("not enough values for ⍣ repeat")),
+        },
+        Primitive::While => match (stack.pop(), stack.pop()) {
+            (
+                Some(body @ (Value::Function(_) | Value::Native(_))),
+                Some(condition @ (Value::Function(_) | Value::Native(_))),
+            ) => loop {
+                caller.call(condition.clone(), stack)?;
+                match stack.pop() {
+                    Some(v) if v.is_truthy() => caller.call(body.clone(), stack)?,
+                    Some(_) => break,
+                    None => {
+                        return Err(JSLError::new(
+                            "the condition of ↻ while left nothing on the stack",
+                        ));
                     }
-                    (Some(x), Some(y)) => {
-                        return Err(JSLError {
-                            msg: format!("cannot join {} and {}", x.type_str(), y.type_str()),
-                        });
+                }
+            },
+            (Some(x), Some(y)) => {
+                return Err(JSLError::new(format!(
+                    "cannot loop with {} as condition and {} as body",
+                    y.type_str(),
+                    x.type_str()
+                )));
+            }
+            _ => return Err(JSLError::new("not enough values for ↻ while")),
+        },
+        Primitive::Map => match (stack.pop(), stack.pop()) {
+            (Some(f @ (Value::Function(_) | Value::Native(_))), Some(target)) => {
+                let Some(elements) = elements(&target) else {
+                    return Err(JSLError::new(format!(
+                        "cannot map over {}",
+                        target.type_str()
+                    )));
+                };
+                let mut res = vec![];
+                for e in elements {
+                    // everything the function leaves ends up in the list, so it can drop
+                    // elements or add more than one
+                    res.extend(call_with(caller, &f, stack, vec![e], "¨ map")?);
+                }
+                stack.push(Value::list(res));
+            }
+            (Some(x), Some(_)) => {
+                return Err(JSLError::new(format!("cannot map with {}", x.type_str())));
+            }
+            _ => return Err(JSLError::new("not enough values for ¨ map")),
+        },
+        Primitive::Filter => match (stack.pop(), stack.pop()) {
+            (Some(f @ (Value::Function(_) | Value::Native(_))), Some(target)) => {
+                let Some(elements) = elements(&target) else {
+                    return Err(JSLError::new(format!(
+                        "cannot filter {}",
+                        target.type_str()
+                    )));
+                };
+                let mut res = vec![];
+                for e in elements {
+                    let keep = call_for_one(caller, &f, stack, vec![e.clone()], "⌿ filter")?;
+                    if keep.is_truthy() {
+                        res.push(e);
                     }
-                    _ => {
-                        return Err(JSLError {
-                            msg: "not enough values for ” join".into(),
-                        });
+                }
+                // filtering a string keeps it a string
+                stack.push(match target {
+                    Value::String(_) => {
+                        Value::string(res.iter().map(|c| c.to_string()).collect::<String>())
                     }
-                },
-                Primitive::Pair => {
-                    let a = stack.pop().unwrap_or(Value::Null);
-                    let b = stack.pop().unwrap_or(Value::Null);
-                    stack.push(Value::List(vec![a, b]));
-                }
-                Primitive::Index => {
-                    match (stack.pop() /* index */, stack.pop() /* target */) {
-                        (Some(Value::Number(i)), Some(Value::List(l))) => {
-                            if i.fract() == 0.0 {
-                                stack.push(
-                                    l.get(if i < 0.0 {
-                                        l.len() - (i as usize)
-                                    } else {
-                                        i as usize
-                                    })
-                                    .unwrap_or(&Value::Null)
-                                    .clone(),
-                                );
-                            } else {
-                                return Err(JSLError {
-                                    msg: "expected integer index".into(),
-                                });
-                            }
-                        }
-                        (Some(Value::Number(i)), Some(Value::String(s))) => {
-                            if i.fract() == 0.0 {
-                                stack.push(
-                                    s.chars()
-                                        .nth(if i < 0.0 {
-                                            s.len() - (i as usize)
-                                        } else {
-                                            i as usize
-                                        })
-                                        .map(|c| Value::String(c.into()))
-                                        .unwrap_or(Value::Null),
-                                );
-                            } else {
-                                return Err(JSLError {
-                                    msg: "expected integer index".into(),
-                                });
-                            }
-                        }
-                        (Some(x), Some(y)) => {
-                            return Err(JSLError {
-                                msg: format!("cannot index {} with {}", y.type_str(), x.type_str()),
-                            });
-                        }
-                        _ => {
-                            return Err(JSLError {
-                                msg: "not enough values for ⤉ index".into(),
-                            });
+                    _ => Value::list(res),
+                });
+            }
+            (Some(x), Some(_)) => {
+                return Err(JSLError::new(format!(
+                    "cannot filter with {}",
+                    x.type_str()
+                )));
+            }
+            _ => return Err(JSLError::new("not enough values for ⌿ filter")),
+        },
+        Primitive::Fold | Primitive::Scan => {
+            let (symbol, name) = match p {
+                Primitive::Fold => ("/ fold", "fold"),
+                _ => ("\\ scan", "scan"),
+            };
+            match (stack.pop(), stack.pop(), stack.pop()) {
+                (Some(f @ (Value::Function(_) | Value::Native(_))), Some(init), Some(target)) => {
+                    let Some(elements) = elements(&target) else {
+                        return Err(JSLError::new(format!(
+                            "cannot {name} {}",
+                            target.type_str()
+                        )));
+                    };
+                    let mut acc = init;
+                    let mut steps = vec![];
+                    for e in elements {
+                        acc = call_for_one(caller, &f, stack, vec![acc, e], symbol)?;
+                        if let Primitive::Scan = p {
+                            steps.push(acc.clone());
                         }
                     }
+                    stack.push(match p {
+                        Primitive::Fold => acc,
+                        _ => Value::list(steps),
+                    });
                 }
-                Primitive::Add => match (stack.pop(), stack.pop()) {
-                    (Some(Value::Number(x)), Some(Value::Number(y))) => {
-                        stack.push(Value::Number(x + y))
-                    }
-                    (Some(x), Some(y)) => {
-                        let use_join_hint = match (x.type_str(), y.type_str()) {
-                            ("string", "string") => true,
-                            ("list", "list") => true,
-                            _ => false,
-                        };
-                        return Err(JSLError {
-                            msg: format!(
-                                "cannot add {} and {}{}",
-                                x.type_str(),
-                                y.type_str(),
-                                if use_join_hint {
-                                    ". perhaps you meant to use ” join?"
-                                } else {
-                                    ""
-                                }
-                            ),
-                        });
-                    }
-                    _ => {
-                        return Err(JSLError {
-                            msg: "not enough values for + add".into(),
-                        });
-                    }
-                },
-                Primitive::Subtract => match (stack.pop(), stack.pop()) {
-                    (Some(Value::Number(x)), Some(Value::Number(y))) => {
-                        stack.push(Value::Number(y - x))
-                    }
-                    (Some(x), Some(y)) => {
-                        return Err(JSLError {
-                            msg: format!("cannot subtract {} from {}", x.type_str(), y.type_str()),
-                        });
-                    }
-                    _ => {
-                        return Err(JSLError {
-                            msg: "not enough values for - subtract".into(),
-                        });
-                    }
-                },
-                Primitive::Multiply => match (stack.pop(), stack.pop()) {
-                    (Some(Value::Number(x)), Some(Value::Number(y))) => {
-                        stack.push(Value::Number(x * y))
-                    }
-                    (Some(x), Some(y)) => {
-                        return Err(JSLError {
-                            msg: format!("cannot multiply {} and {}", x.type_str(), y.type_str()),
-                        });
-                    }
-                    _ => {
-                        return Err(JSLError {
-                            msg: "not enough values for × multiply".into(),
-                        });
-                    }
-                },
-                Primitive::Divide => match (stack.pop(), stack.pop()) {
-                    (Some(Value::Number(x)), Some(Value::Number(y))) => {
-                        stack.push(Value::Number(y / x))
-                    }
-                    (Some(x), Some(y)) => {
-                        return Err(JSLError {
-                            msg: format!("cannot divide {} by {}", y.type_str(), x.type_str()),
-                        });
-                    }
+                (Some(x), Some(_), Some(_)) => {
+                    return Err(JSLError::new(format!(
+                        "cannot {name} with {}",
+                        x.type_str()
+                    )));
+                }
+                _ => return Err(JSLError::new(format!("not enough values for {symbol}"))),
+            }
+        }
+        Primitive::Over => match stack.len() {
+            // a b → a b a
+            2.. => stack.push(stack[stack.len() - 2].clone()),
+            _ => return Err(JSLError::new("not enough values for ⇈ over")),
+        },
+        Primitive::Rotate => match stack.len() {
+            // a b c → b c a
+            3.. => {
+                let a = stack.remove(stack.len() - 3);
+                stack.push(a);
+            }
+            _ => return Err(JSLError::new("not enough values for ⟳ rotate")),
+        },
+        Primitive::Nip => match stack.len() {
+            // a b → b
+            2.. => {
+                stack.remove(stack.len() - 2);
+            }
+            _ => return Err(JSLError::new("not enough values for ⊣ nip")),
+        },
+        Primitive::Tuck => match stack.len() {
+            // a b → b a b
+            2.. => stack.insert(stack.len() - 2, stack[stack.len() - 1].clone()),
+            _ => return Err(JSLError::new("not enough values for ⊢ tuck")),
+        },
+        Primitive::Dip => match (stack.pop(), stack.pop()) {
+            // hides x while f runs
+            (Some(f @ (Value::Function(_) | Value::Native(_))), Some(x)) => {
+                caller.call(f, stack)?;
+                stack.push(x);
+            }
+            (Some(x), Some(_)) => {
+                return Err(JSLError::new(format!("cannot dip with {}", x.type_str())));
+            }
+            _ => return Err(JSLError::new("not enough values for ⊸ dip")),
+        },
+        Primitive::Keep => match (stack.pop(), stack.last().cloned()) {
+            // runs f on x, then gives x back
+            (Some(f @ (Value::Function(_) | Value::Native(_))), Some(x)) => {
+                caller.call(f, stack)?;
+                stack.push(x);
+            }
+            (Some(x), Some(_)) => {
+                return Err(JSLError::new(format!("cannot keep with {}", x.type_str())));
+            }
+            _ => return Err(JSLError::new("not enough values for ⊙ keep")),
+        },
+        Primitive::Depth => stack.push(Value::Number(stack.len() as f64)),
+        Primitive::Clear => stack.clear(),
+        Primitive::Stack => stack.push(Value::list(stack.clone())),
+        Primitive::Unstack => match stack.pop() {
+            Some(Value::List(l)) => *stack = l.to_vec(),
+            Some(x) => {
+                return Err(JSLError::new(format!(
+                    "cannot replace the stack with {}",
+                    x.type_str()
+                )));
+            }
+            None => return Err(JSLError::new("not enough values for ⊐ unstack")),
+        },
+        Primitive::TypeOf => match stack.pop() {
+            Some(x) => stack.push(Value::string(x.type_str())),
+            None => return Err(JSLError::new("not enough values for ⍰ type of")),
+        },
+        Primitive::ParseNumber => match stack.pop() {
+            Some(Value::String(s)) => {
+                // bad input isn't an error, so programs can check for it
+                stack.push(parse_number(&s).map_or(Value::Null, Value::Number))
+            }
+            Some(x) => {
+                return Err(JSLError::new(format!(
+                    "cannot parse a number from {}",
+                    x.type_str()
+                )));
+            }
+            None => return Err(JSLError::new("not enough values for ⍎ parse number")),
+        },
+        Primitive::ToString => match stack.pop() {
+            Some(x) => stack.push(Value::string(x.to_string())),
+            None => return Err(JSLError::new("not enough values for ⍕ to string")),
+        },
+        Primitive::Repr => match stack.pop() {
+            Some(x) => stack.push(Value::string(format!("{x:?}"))),
+            None => return Err(JSLError::new("not enough values for ⌗ representation")),
+        },
+        Primitive::Length => match stack.pop() {
+            Some(Value::List(l)) => stack.push(Value::Number(l.len() as f64)),
+            Some(Value::String(s)) => stack.push(Value::Number(s.chars().count() as f64)),
+            Some(Value::Map(m)) => stack.push(Value::Number(m.len() as f64)),
+            Some(x) => {
+                return Err(JSLError::new(format!(
+                    "cannot get the length of {}",
+                    x.type_str()
+                )));
+            }
+            None => return Err(JSLError::new("not enough values for ≢ length")),
+        },
+        Primitive::Range => {
+            let (start, end, step) = match stack.pop() {
+                Some(Value::Number(n)) => (0.0, n, 1.0),
+                Some(Value::List(l)) => match l.as_slice() {
+                    [Value::Number(start), Value::Number(end)] => (*start, *end, 1.0),
+                    [
+                        Value::Number(start),
+                        Value::Number(end),
+                        Value::Number(step),
+                    ] => (*start, *end, *step),
                     _ => {
-                        return Err(JSLError {
-                            msg: "not enough values for ÷ divide".into(),
-                        });
+                        return Err(JSLError::new(
+                            "expected [ start end ] or [ start end step ] for ⍳ range",
+                        ));
                     }
                 },
-                Primitive::Equals => match (stack.pop(), stack.pop()) {
-                    (Some(x), Some(y)) => stack.push(Value::Number((x == y).into())),
+                Some(x) => {
+                    return Err(JSLError::new(format!(
+                        "cannot make a range from {}",
+                        x.type_str()
+                    )));
+                }
+                None => return Err(JSLError::new("not enough values for ⍳ range")),
+            };
+            if step == 0.0 || !(start.is_finite() && end.is_finite() && step.is_finite()) {
+                return Err(JSLError::new(
+                    "⍳ range needs finite numbers and a step that isn't 0",
+                ));
+            }
+            // checked as a float first, casting 1e300 to usize just saturates
+            let count = ((end - start) / step).ceil().max(0.0);
+            if count > MAX_RANGE_LEN {
+                return Err(JSLError::new("⍳ range is too long"));
+            }
+            stack.push(Value::list(
+                (0..count as usize)
+                    .map(|i| Value::Number(start + i as f64 * step))
+                    .collect(),
+            ));
+        }
+        Primitive::Reverse => match stack.pop() {
+            Some(Value::List(mut l)) => {
+                Rc::make_mut(&mut l).reverse();
+                stack.push(Value::List(l));
+            }
+            Some(Value::String(s)) => {
+                stack.push(Value::string(s.chars().rev().collect::<String>()))
+            }
+            Some(x) => return Err(JSLError::new(format!("cannot reverse {}", x.type_str()))),
+            None => return Err(JSLError::new("not enough values for ⌽ reverse")),
+        },
+        Primitive::Take | Primitive::Drop => {
+            let (symbol, name) = match p {
+                Primitive::Take => ("↑ take", "take"),
+                _ => ("↓ drop", "drop"),
+            };
+            match (stack.pop(), stack.pop()) {
+                (Some(Value::Number(n)), Some(target)) => {
+                    let len = length(&target).ok_or_else(|| {
+                        JSLError::new(format!("cannot {name} from {}", target.type_str()))
+                    })?;
+                    // negative counts work from the end
+                    let split = clamp_index(n, len)?;
+                    let (start, end) = match (p, n < 0.0) {
+                        (Primitive::Take, false) | (Primitive::Drop, true) => (0, split),
+                        _ => (split, len),
+                    };
+                    stack.push(slice(&target, start, end));
+                }
+                (Some(x), Some(y)) => {
+                    return Err(JSLError::new(format!(
+                        "cannot {name} {} from {}",
+                        x.type_str(),
+                        y.type_str()
+                    )));
+                }
+                _ => return Err(JSLError::new(format!("not enough values for {symbol}"))),
+            }
+        }
+        Primitive::Slice => match (stack.pop(), stack.pop(), stack.pop()) {
+            (Some(Value::Number(end)), Some(Value::Number(start)), Some(target)) => {
+                let len = length(&target)
+                    .ok_or_else(|| JSLError::new(format!("cannot slice {}", target.type_str())))?;
+                let start = clamp_index(start, len)?;
+                let end = clamp_index(end, len)?.max(start);
+                stack.push(slice(&target, start, end));
+            }
+            (Some(_), Some(_), Some(_)) => {
+                return Err(JSLError::new("expected a start and end index for ✂ slice"));
+            }
+            _ => return Err(JSLError::new("not enough values for ✂ slice")),
+        },
+        Primitive::Split => string_pair(stack, "⊘ split", "split", "by", |s, sep| {
+            let parts: Vec<Value> = if sep.is_empty() {
+                // nothing to split by, so every character is its own piece
+                s.chars().map(Value::from).collect()
+            } else {
+                s.split(sep).map(Value::string).collect()
+            };
+            Ok(Value::list(parts))
+        })?,
+        Primitive::JoinWith => match (stack.pop(), stack.pop()) {
+            (Some(Value::String(sep)), Some(Value::List(l))) => {
+                let parts: Vec<String> = l.iter().map(|v| v.to_string()).collect();
+                stack.push(Value::string(parts.join(sep.as_str())));
+            }
+            (Some(x), Some(y)) => {
+                return Err(JSLError::new(format!(
+                    "cannot join {} with {}",
+                    y.type_str(),
+                    x.type_str()
+                )));
+            }
+            _ => return Err(JSLError::new("not enough values for ⊕ join with")),
+        },
+        Primitive::IndexOf => string_pair(stack, "⍸ index of", "search", "for", |s, needle| {
+            // find gives a byte offset, but everything else counts characters
+            Ok(s.find(needle).map_or(
+                Value::Null,
+                |i| Value::Number(s[..i].chars().count() as f64),
+            ))
+        })?,
+        Primitive::Contains => string_pair(stack, "∊ contains", "search", "for", |s, needle| {
+            Ok(Value::Number(s.contains(needle) as u8 as f64))
+        })?,
+        Primitive::StartsWith => string_pair(
+            stack,
+            "⊲ starts with",
+            "check the start of",
+            "for",
+            |s, prefix| Ok(Value::Number(s.starts_with(prefix) as u8 as f64)),
+        )?,
+        Primitive::EndsWith => string_pair(
+            stack,
+            "⊳ ends with",
+            "check the end of",
+            "for",
+            |s, suffix| Ok(Value::Number(s.ends_with(suffix) as u8 as f64)),
+        )?,
+        Primitive::Replace => match (stack.pop(), stack.pop(), stack.pop()) {
+            (Some(Value::String(new)), Some(Value::String(old)), Some(Value::String(s))) => {
+                if old.is_empty() {
+                    return Err(JSLError::new("cannot replace an empty string"));
+                }
+                stack.push(Value::string(s.replace(old.as_str(), &new)));
+            }
+            (Some(_), Some(_), Some(_)) => {
+                return Err(JSLError::new(
+                    "expected a string, what to replace and its replacement for ⇄ replace",
+                ));
+            }
+            _ => return Err(JSLError::new("not enough values for ⇄ replace")),
+        },
+        Primitive::Upper => string_map(stack, "⇡ uppercase", "uppercase", |s| s.to_uppercase())?,
+        Primitive::Lower => string_map(stack, "⇣ lowercase", "lowercase", |s| s.to_lowercase())?,
+        Primitive::Trim => string_map(stack, "⌦ trim", "trim", |s| s.trim().into())?,
+        Primitive::CodePoint => match stack.pop() {
+            Some(Value::String(s)) => {
+                let mut chars = s.chars();
+                match (chars.next(), chars.next()) {
+                    (Some(c), None) => stack.push(Value::Number(c as u32 as f64)),
                     _ => {
-                        return Err(JSLError {
-                            msg: "not enough values for = equals".into(),
-                        });
+                        return Err(JSLError::new(format!(
+                            "⊶ code point expects a single character, not {s:?}"
+                        )));
                     }
-                },
-                #[allow(unreachable_patterns)]
-                _ => todo!(),
-            },
+                }
+            }
+            Some(x) => {
+                return Err(JSLError::new(format!(
+                    "cannot get the code point of {}",
+                    x.type_str()
+                )));
+            }
+            None => return Err(JSLError::new("not enough values for ⊶ code point")),
+        },
+        Primitive::Character => match stack.pop() {
+            Some(Value::Number(n)) => {
+                // surrogates and anything past 10FFFF aren't characters
+                let c = (n.fract() == 0.0 && n >= 0.0 && n <= u32::MAX as f64)
+                    .then(|| char::from_u32(n as u32))
+                    .flatten()
+                    .ok_or_else(|| {
+                        JSLError::new(format!("{:?} is not a valid code point", Value::Number(n)))
+                    })?;
+                stack.push(Value::from(c));
+            }
+            Some(x) => {
+                return Err(JSLError::new(format!(
+                    "cannot make a character from {}",
+                    x.type_str()
+                )));
+            }
+            None => return Err(JSLError::new("not enough values for ⊷ character")),
+        },
+        // these reuse the map when nothing else is holding on to it, just like ” does with lists
+        Primitive::Insert => match (stack.pop(), stack.pop(), stack.pop()) {
+            (Some(value), Some(key), Some(Value::Map(mut m))) => {
+                Rc::make_mut(&mut m).insert(map_key(&key)?, value);
+                stack.push(Value::Map(m));
+            }
+            (Some(_), Some(_), Some(x)) => {
+                return Err(JSLError::new(format!(
+                    "cannot insert into {}",
+                    x.type_str()
+                )));
+            }
+            _ => return Err(JSLError::new("not enough values for ⤈ insert")),
+        },
+        Primitive::Remove => match (stack.pop(), stack.pop()) {
+            (Some(key), Some(Value::Map(mut m))) => {
+                let key = map_key(&key)?;
+                // no need to copy anything if it isn't there
+                if m.contains_key(&key) {
+                    Rc::make_mut(&mut m).remove(&key);
+                }
+                stack.push(Value::Map(m));
+            }
+            (Some(_), Some(x)) => {
+                return Err(JSLError::new(format!(
+                    "cannot remove from {}",
+                    x.type_str()
+                )));
+            }
+            _ => return Err(JSLError::new("not enough values for ⤓ remove")),
+        },
+        Primitive::HasKey => match (stack.pop(), stack.pop()) {
+            (Some(key), Some(Value::Map(m))) => {
+                let has = m.contains_key(&map_key(&key)?);
+                stack.push(Value::Number(has as u8 as f64));
+            }
+            (Some(_), Some(x)) => {
+                return Err(JSLError::new(format!(
+                    "cannot look for a key in {}",
+                    x.type_str()
+                )));
+            }
+            _ => return Err(JSLError::new("not enough values for ∋ has key")),
+        },
+        Primitive::Keys | Primitive::Values | Primitive::Entries => {
+            let (symbol, name) = match p {
+                Primitive::Keys => ("⊤ keys", "keys"),
+                Primitive::Values => ("⊥ values", "values"),
+                _ => ("⋈ entries", "entries"),
+            };
+            match stack.pop() {
+                Some(Value::Map(m)) => {
+                    let out = m.iter().map(|(k, v)| match p {
+                        Primitive::Keys => k.to_value(),
+                        Primitive::Values => v.clone(),
+                        _ => Value::list(vec![k.to_value(), v.clone()]),
+                    });
+                    stack.push(Value::list(out.collect()));
+                }
+                Some(x) => {
+                    return Err(JSLError::new(format!(
+                        "cannot get the {name} of {}",
+                        x.type_str()
+                    )));
+                }
+                None => return Err(JSLError::new(format!("not enough values for {symbol}"))),
+            }
+        }
+        Primitive::Call => unreachable!(), // handled by run_ast
+    }
+    Ok(())
+}
+
+// the two functions can come from different scopes, so instead of gluing their bodies together
+// this makes a function that calls one and then the other: { a! b! }
+fn compose(a: Value, b: Value) -> Closure {
+    let literal_span = |f: &Value| match f {
+        Value::Function(closure) => closure.func.span,
+        _ => None,
+    };
+    let defined_at = literal_span(&a).or(literal_span(&b));
+    let span = defined_at.unwrap_or_default();
+    let node = |statement| Node { statement, span };
+    let body = vec![
+        node(Statement::Literal(a)),
+        node(Statement::Primitive(Primitive::Call)),
+        node(Statement::Literal(b)),
+        node(Statement::Primitive(Primitive::Call)),
+    ];
+    Closure {
+        func: Rc::new(Function::new(body, defined_at)),
+        env: Scope::root(),
+    }
+}
+
+fn map_key(v: &Value) -> JSLResult<Key> {
+    Key::from_value(v)
+        .ok_or_else(|| JSLError::new(format!("cannot use {} as a map key", v.type_str())))
+}
+
+fn pop_path(stack: &mut Vec<Value>, symbol: &str, name: &str) -> JSLResult<Rc<String>> {
+    match stack.pop() {
+        Some(Value::String(path)) => Ok(path),
+        Some(x) => Err(JSLError::new(format!(
+            "{name} expects a path string, not {}",
+            x.type_str()
+        ))),
+        None => Err(JSLError::new(format!("not enough values for {symbol}"))),
+    }
+}
+
+// the os knows best what went wrong, so just pass that along
+fn fs_error(action: &str, path: &Rc<String>, e: std::io::Error) -> JSLError {
+    JSLError::new(format!(
+        "could not {action} {:?}: {e}",
+        Value::String(path.clone())
+    ))
+}
+
+// pops two numbers and pushes f(below, top), erroring just like the basic arithmetic does
+fn binary_math(
+    stack: &mut Vec<Value>,
+    symbol: &str,
+    verb: &str,
+    preposition: &str,
+    f: fn(f64, f64) -> f64,
+) -> JSLResult<()> {
+    match (stack.pop(), stack.pop()) {
+        (Some(Value::Number(x)), Some(Value::Number(y))) => stack.push(Value::Number(f(y, x))),
+        (Some(x), Some(y)) => {
+            return Err(JSLError::new(format!(
+                "cannot {verb} {} {preposition} {}",
+                y.type_str(),
+                x.type_str()
+            )));
+        }
+        _ => {
+            return Err(JSLError::new(format!("not enough values for {symbol}")));
+        }
+    }
+    Ok(())
+}
+
+// pops a number and pushes f of it
+fn unary_math(
+    stack: &mut Vec<Value>,
+    symbol: &str,
+    verb: &str,
+    f: fn(f64) -> f64,
+) -> JSLResult<()> {
+    match stack.pop() {
+        Some(Value::Number(n)) => stack.push(Value::Number(f(n))),
+        Some(x) => {
+            return Err(JSLError::new(format!("cannot {verb} {}", x.type_str())));
+        }
+        None => {
+            return Err(JSLError::new(format!("not enough values for {symbol}")));
         }
     }
     Ok(())
 }
+
+// pops two strings and pushes f(below, top)
+fn string_pair(
+    stack: &mut Vec<Value>,
+    symbol: &str,
+    verb: &str,
+    preposition: &str,
+    f: fn(&str, &str) -> JSLResult<Value>,
+) -> JSLResult<()> {
+    match (stack.pop(), stack.pop()) {
+        (Some(Value::String(x)), Some(Value::String(y))) => stack.push(f(&y, &x)?),
+        (Some(x), Some(y)) => {
+            return Err(JSLError::new(format!(
+                "cannot {verb} {} {preposition} {}",
+                y.type_str(),
+                x.type_str()
+            )));
+        }
+        _ => {
+            return Err(JSLError::new(format!("not enough values for {symbol}")));
+        }
+    }
+    Ok(())
+}
+
+// pops a string and pushes f of it
+fn string_map(
+    stack: &mut Vec<Value>,
+    symbol: &str,
+    verb: &str,
+    f: fn(&str) -> String,
+) -> JSLResult<()> {
+    match stack.pop() {
+        Some(Value::String(s)) => stack.push(Value::string(f(&s))),
+        Some(x) => {
+            return Err(JSLError::new(format!("cannot {verb} {}", x.type_str())));
+        }
+        None => {
+            return Err(JSLError::new(format!("not enough values for {symbol}")));
+        }
+    }
+    Ok(())
+}
+
+/// turns a (maybe negative, counting from the end) index into a position, if it's in bounds
+pub(crate) fn resolve_index(i: f64, len: usize) -> JSLResult<Option<usize>> {
+    if i.fract() != 0.0 {
+        return Err(JSLError::new("expected integer index"));
+    }
+    let i = if i < 0.0 { len as f64 + i } else { i };
+    Ok((i >= 0.0 && i < len as f64).then_some(i as usize))
+}
+
+/// like `resolve_index`, but for the edges between elements, so out of bounds just gets clamped
+pub(crate) fn clamp_index(i: f64, len: usize) -> JSLResult<usize> {
+    if i.fract() != 0.0 {
+        return Err(JSLError::new("expected integer index"));
+    }
+    let i = if i < 0.0 { len as f64 + i } else { i };
+    Ok(i.clamp(0.0, len as f64) as usize)
+}
+
+// in elements for lists, in characters for strings
+fn length(v: &Value) -> Option<usize> {
+    match v {
+        Value::List(l) => Some(l.len()),
+        Value::String(s) => Some(s.chars().count()),
+        _ => None,
+    }
+}
+
+// only call this with a list or string, and start <= end <= length
+fn slice(v: &Value, start: usize, end: usize) -> Value {
+    match v {
+        Value::List(l) => Value::list(l[start..end].to_vec()),
+        Value::String(s) => {
+            Value::string(s.chars().skip(start).take(end - start).collect::<String>())
+        }
+        _ => unreachable!(),
+    }
+}
+
+// strict indexing errors when out of bounds, the regular kind gives ∅ instead
+fn index(target: Value, i: Value, strict: bool) -> JSLResult<Value> {
+    let out_of_bounds = |i: f64, len: usize| {
+        JSLError::new(format!(
+            "index {:?} is out of bounds for length {len}",
+            Value::Number(i)
+        ))
+    };
+    match (i, target) {
+        (Value::Number(i), Value::List(l)) => match resolve_index(i, l.len())? {
+            Some(i) => Ok(l[i].clone()),
+            None if strict => Err(out_of_bounds(i, l.len())),
+            None => Ok(Value::Null),
+        },
+        // strings get indexed by character, not by byte
+        (Value::Number(i), Value::String(s)) => {
+            let len = s.chars().count();
+            match resolve_index(i, len)? {
+                Some(i) => Ok(s.chars().nth(i).unwrap().into()),
+                None if strict => Err(out_of_bounds(i, len)),
+                None => Ok(Value::Null),
+            }
+        }
+        // maps get indexed by key instead, picking several with a list of keys like everything else
+        (Value::List(keys), Value::Map(m)) => Ok(Value::list(
+            keys.iter()
+                .map(|k| index(Value::Map(m.clone()), k.clone(), strict))
+                .collect::<JSLResult<_>>()?,
+        )),
+        (k, Value::Map(m)) => match m.get(&map_key(&k)?) {
+            Some(v) => Ok(v.clone()),
+            None if strict => Err(JSLError::new(format!("{k:?} is not a key of the map"))),
+            None => Ok(Value::Null),
+        },
+        // a list of indices picks out several things at once
+        (Value::List(indices), Value::List(l)) => Ok(Value::list(
+            indices
+                .iter()
+                .map(|i| index(Value::List(l.clone()), i.clone(), strict))
+                .collect::<JSLResult<_>>()?,
+        )),
+        // ...which for strings makes another string, skipping anything out of bounds
+        (Value::List(indices), Value::String(s)) => {
+            let chars: Vec<char> = s.chars().collect();
+            let mut res = String::new();
+            for i in indices.iter() {
+                let Value::Number(i) = i else {
+                    return Err(JSLError::new("expected integer index"));
+                };
+                match resolve_index(*i, chars.len())? {
+                    Some(i) => res.push(chars[i]),
+                    None if strict => return Err(out_of_bounds(*i, chars.len())),
+                    None => (),
+                }
+            }
+            Ok(Value::string(res))
+        }
+        (x, y) => Err(JSLError::new(format!(
+            "cannot index {} with {}",
+            y.type_str(),
+            x.type_str()
+        ))),
+    }
+}
+
+/// numbers by value, strings by character, lists element by element. None means NaN was involved
+pub(crate) fn compare(x: &Value, y: &Value) -> JSLResult<Option<Ordering>> {
+    match (x, y) {
+        (Value::Number(x), Value::Number(y)) => Ok(x.partial_cmp(y)),
+        (Value::String(x), Value::String(y)) => Ok(Some(x.cmp(y))),
+        (Value::List(x), Value::List(y)) => {
+            for (a, b) in x.iter().zip(y.iter()) {
+                match compare(a, b)? {
+                    Some(Ordering::Equal) => (),
+                    other => return Ok(other),
+                }
+            }
+            Ok(Some(x.len().cmp(&y.len())))
+        }
+        (x, y) => Err(JSLError::new(format!(
+            "cannot compare {} and {}",
+            x.type_str(),
+            y.type_str()
+        ))),
+    }
+}
+
+// what higher-order primitives go over: lists, and strings as lists of characters
+fn elements(v: &Value) -> Option<Vec<Value>> {
+    match v {
+        Value::List(l) => Some(l.to_vec()),
+        Value::String(s) => Some(s.chars().map(Value::from).collect()),
+        _ => None,
+    }
+}
+
+// pushes the arguments, calls the function, and takes back whatever it left
+fn call_with(
+    caller: &mut dyn Caller,
+    f: &Value,
+    stack: &mut Vec<Value>,
+    args: Vec<Value>,
+    symbol: &str,
+) -> JSLResult<Vec<Value>> {
+    let base = stack.len();
+    stack.extend(args);
+    caller.call(f.clone(), stack)?;
+    if stack.len() < base {
+        return Err(JSLError::new(format!(
+            "the function given to {symbol} took more values than it was given"
+        )));
+    }
+    Ok(stack.split_off(base))
+}
+
+// same thing, for when the function has to leave exactly one value
+fn call_for_one(
+    caller: &mut dyn Caller,
+    f: &Value,
+    stack: &mut Vec<Value>,
+    args: Vec<Value>,
+    symbol: &str,
+) -> JSLResult<Value> {
+    let mut res = call_with(caller, f, stack, args, symbol)?;
+    match res.len() {
+        1 => Ok(res.pop().unwrap()),
+        n => Err(JSLError::new(format!(
+            "the function given to {symbol} has to leave exactly one value, but it left {n}"
+        ))),
+    }
+}
@@ -0,0 +1,73 @@
+use super::{compile::intern, value::*};
+use std::{cell::RefCell, collections::HashMap, rc::Rc};
+
+/// the bindings of one function call (or of the top level), plus the scope it was defined in
+#[derive(Default)]
+pub struct Scope {
+    vars: RefCell<HashMap<u32, Binding>>,
+    parent: Option<Env>,
+}
+
+pub type Env = Rc<Scope>;
+
+// a function bound in the same scope it was written in (like a local helper, or anything
+// recursive) would point back at the scope holding it, and the two would keep each other alive
+// forever. so those lose their scope while they're in here, and get it back when they're read.
+// (functions tucked away in a list, or made by another call in here, can still do this)
+enum Binding {
+    Value(Value),
+    Local(Rc<Function>),
+}
+
+impl Scope {
+    pub(crate) fn root() -> Env {
+        Rc::new(Scope::default())
+    }
+
+    pub(crate) fn child(parent: &Env) -> Env {
+        Rc::new(Scope {
+            vars: RefCell::new(HashMap::new()),
+            parent: Some(parent.clone()),
+        })
+    }
+
+    /// looks an identifier up here, then in every scope around this one
+    pub(crate) fn get(self: &Env, id: u32) -> Value {
+        let mut scope = self;
+        loop {
+            match scope.vars.borrow().get(&id) {
+                Some(Binding::Value(v)) => return v.clone(),
+                Some(Binding::Local(func)) => {
+                    return Value::Function(Closure {
+                        func: func.clone(),
+                        env: scope.clone(),
+                    });
+                }
+                None => (),
+            }
+            match &scope.parent {
+                Some(parent) => scope = parent,
+                None => return Value::Null,
+            }
+        }
+    }
+
+    /// binds in this scope only, shadowing anything outside of it
+    pub(crate) fn set(&self, id: u32, value: Value) {
+        let binding = match value {
+            Value::Function(closure) if std::ptr::eq(Rc::as_ptr(&closure.env), self) => {
+                Binding::Local(closure.func)
+            }
+            value => Binding::Value(value),
+        };
+        self.vars.borrow_mut().insert(id, binding);
+    }
+
+    pub(crate) fn get_named(self: &Env, name: &str) -> Value {
+        self.get(intern(name))
+    }
+
+    pub(crate) fn set_named(&self, name: &str, value: Value) {
+        self.set(intern(name), value)
+    }
+}
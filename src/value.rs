@@ -1,34 +1,223 @@
-use std::fmt;
+use std::{cell::OnceCell, cmp::Ordering, collections::BTreeMap, fmt, rc::Rc};
 
-use super::parse;
+use super::{compile, parse, result::*, scope::Env};
 
+/// a function body, plus its bytecode once something has needed it
+pub struct Function {
+    pub(crate) body: parse::AST,
+    pub(crate) span: Option<Span>, // of the { ... } it was written as, for traces
+    code: OnceCell<compile::Chunk>,
+}
+
+impl Function {
+    pub(crate) fn new(body: parse::AST, span: Option<Span>) -> Self {
+        Function {
+            body,
+            span,
+            code: OnceCell::new(),
+        }
+    }
+
+    // compiled lazily, so functions that never get called never get compiled
+    pub(crate) fn chunk(&self) -> &compile::Chunk {
+        self.code.get_or_init(|| compile::compile(&self.body))
+    }
+}
+
+/// a function, plus the scope it was written in
 #[derive(Clone)]
+pub struct Closure {
+    pub(crate) func: Rc<Function>,
+    pub(crate) env: Env,
+}
+
+// what a rust function looks like to jsl
+type NativeFn = dyn Fn(&mut Vec<Value>) -> JSLResult<()>;
+
+/// a function written in rust, that jsl code can call just like its own functions
+pub struct NativeFunction {
+    name: String,
+    func: Box<NativeFn>,
+}
+
+impl NativeFunction {
+    /// the function gets the whole stack, and should pop its arguments and push its results.
+    /// `name` shows up in traces when it errors
+    pub fn new(
+        name: impl Into<String>,
+        func: impl Fn(&mut Vec<Value>) -> JSLResult<()> + 'static,
+    ) -> Self {
+        NativeFunction {
+            name: name.into(),
+            func: Box::new(func),
+        }
+    }
+
+    /// the name it was made with
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    // `call_site` being the ! (or whatever primitive called it), for errors
+    pub(crate) fn call(&self, stack: &mut Vec<Value>, call_site: Span) -> JSLResult<()> {
+        (self.func)(stack).map_err(|e| {
+            e.at(call_site).traced(TraceFrame::Call {
+                name: Some(self.name.clone()),
+                defined_at: None,
+                call_site,
+            })
+        })
+    }
+}
+
+/// what maps can be keyed by
+#[derive(Clone)]
+#[non_exhaustive]
+pub enum Key {
+    /// a number. `¯0` is the same key as `0`, and every `NaN` is the same key
+    Number(f64),
+    /// a string
+    String(Rc<String>),
+}
+
+impl Key {
+    /// the key for a value, if it can be one
+    pub fn from_value(value: &Value) -> Option<Key> {
+        match value {
+            // so the map doesn't remember which zero it was given first
+            Value::Number(n) if *n == 0.0 => Some(Key::Number(0.0)),
+            Value::Number(n) => Some(Key::Number(*n)),
+            Value::String(s) => Some(Key::String(s.clone())),
+            _ => None,
+        }
+    }
+
+    /// the key as a value again
+    pub fn to_value(&self) -> Value {
+        match self {
+            Key::Number(n) => Value::Number(*n),
+            Key::String(s) => Value::String(s.clone()),
+        }
+    }
+}
+
+// numbers come before strings, and numbers are compared by value (so they need some help to
+// be totally ordered)
+impl Ord for Key {
+    fn cmp(&self, other: &Self) -> Ordering {
+        let normalize = |n: f64| {
+            if n.is_nan() {
+                f64::NAN
+            } else if n == 0.0 {
+                0.0
+            } else {
+                n
+            }
+        };
+        match (self, other) {
+            (Key::Number(x), Key::Number(y)) => normalize(*x).total_cmp(&normalize(*y)),
+            (Key::String(x), Key::String(y)) => x.cmp(y),
+            (Key::Number(_), Key::String(_)) => Ordering::Less,
+            (Key::String(_), Key::Number(_)) => Ordering::Greater,
+        }
+    }
+}
+
+impl PartialOrd for Key {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for Key {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Key {}
+
+impl fmt::Debug for Key {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{:?}", self.to_value())
+    }
+}
+
+/// anything that can go on the stack
+#[derive(Clone)]
+#[non_exhaustive]
 pub enum Value {
+    /// every number is a float, integers included
     Number(f64),
-    String(String),
-    Function(parse::AST),
-    List(Vec<Value>),
+    // everything bigger than a number is shared, so copying a value is always cheap
+    /// a string. [`Value::string`] saves you the `Rc::new`
+    String(Rc<String>),
+    /// a function (with the scope it was made in). these can only be made by running code
+    Function(Closure),
+    /// a function written in rust. it's a function as far as jsl code can tell
+    Native(Rc<NativeFunction>),
+    /// a list. [`Value::list`] saves you the `Rc::new`
+    List(Rc<Vec<Value>>),
+    /// a map, sorted by key. [`Value::map`] saves you the `Rc::new`
+    Map(Rc<BTreeMap<Key, Value>>),
+    /// `∅`
     Null,
 }
 
 impl Value {
+    /// makes a [`Value::String`]
+    pub fn string(s: impl Into<String>) -> Self {
+        Value::String(Rc::new(s.into()))
+    }
+
+    /// makes a [`Value::List`]
+    pub fn list(l: Vec<Value>) -> Self {
+        Value::List(Rc::new(l))
+    }
+
+    /// makes a [`Value::Map`]
+    pub fn map(m: BTreeMap<Key, Value>) -> Self {
+        Value::Map(Rc::new(m))
+    }
+
+    /// ∅, 0, NaN, "", □ and ⊡ are false, everything else is true
+    pub fn is_truthy(&self) -> bool {
+        match self {
+            Value::Number(n) => *n != 0.0 && !n.is_nan(),
+            Value::String(s) => !s.is_empty(),
+            Value::List(l) => !l.is_empty(),
+            Value::Map(m) => !m.is_empty(),
+            Value::Function(_) | Value::Native(_) => true,
+            Value::Null => false,
+        }
+    }
+
+    /// the name of the value's type, which is what `⍰` outputs
     pub fn type_str(&self) -> &str {
         match self {
             Value::Number(_) => "number",
             Value::String(_) => "string",
             Value::List(_) => "list",
-            Value::Function(_) => "function",
+            Value::Map(_) => "map",
+            Value::Function(_) | Value::Native(_) => "function",
             Value::Null => "null",
         }
     }
 }
 
+impl From<char> for Value {
+    fn from(c: char) -> Self {
+        Value::string(c)
+    }
+}
+
 impl PartialEq for Value {
     fn eq(&self, other: &Self) -> bool {
         match (self, other) {
             (Value::Number(x), Value::Number(y)) => x == y,
             (Value::String(x), Value::String(y)) => x == y,
             (Value::List(x), Value::List(y)) => x == y,
+            (Value::Map(x), Value::Map(y)) => x == y,
             (Value::Null, Value::Null) => true,
             _ => false,
         }
@@ -38,14 +227,18 @@ impl PartialEq for Value {
 impl fmt::Debug for Value {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self {
+            // written the same way number literals are, so they can be read back in
             Value::Number(n) => {
+                if n.is_nan() {
+                    return write!(f, "NaN");
+                }
+                if n.is_sign_negative() {
+                    write!(f, "¯")?;
+                }
                 if n.is_infinite() {
-                    if n.is_sign_negative() {
-                        write!(f, "-")?;
-                    }
                     write!(f, "∞")
                 } else {
-                    write!(f, "{n}")
+                    write!(f, "{}", n.abs())
                 }
             }
             Value::String(s) => {
@@ -63,14 +256,22 @@ impl fmt::Debug for Value {
                 }
                 write!(f, "\"")
             }
-            Value::Function(_) => write!(f, "{{…}}"),
+            Value::Function(_) | Value::Native(_) => write!(f, "{{…}}"),
             Value::List(l) => {
                 write!(f, "[ ")?;
-                for e in l {
+                for e in l.iter() {
                     write!(f, "{e:?} ")?;
                 }
                 write!(f, "]")
             }
+            // like lists, with a : between each key and its value
+            Value::Map(m) => {
+                write!(f, "⟨ ")?;
+                for (k, v) in m.iter() {
+                    write!(f, "{k:?}: {v:?} ")?;
+                }
+                write!(f, "⟩")
+            }
             Value::Null => write!(f, "∅"),
         }
     }
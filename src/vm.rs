@@ -0,0 +1,186 @@
+use super::{
+    compile::*,
+    io::Io,
+    parse::*,
+    result::*,
+    run::{Caller, run_primitive},
+    scope::*,
+    value::*,
+};
+use std::rc::Rc;
+
+struct Frame {
+    func: Rc<Function>,
+    ip: usize,
+    env: Env,
+    elided: usize,
+    entry: Option<TraceFrame>, // how we got here, None for top-level code
+}
+
+/// runs compiled code. this does the same thing as `run::run_ast`, just faster
+pub(crate) struct Vm {
+    pub(crate) globals: Env,
+    frames: Vec<Frame>,
+    site: Span, // where the primitive that's running is, for calls made by it
+    pub(crate) io: Io,
+}
+
+impl Default for Vm {
+    fn default() -> Self {
+        Vm {
+            globals: Scope::root(),
+            frames: vec![],
+            site: Span::default(),
+            io: Io::default(),
+        }
+    }
+}
+
+impl Vm {
+    pub(crate) fn run(&mut self, ast: AST, stack: &mut Vec<Value>) -> JSLResult<()> {
+        let base = self.frames.len();
+        self.frames.push(Frame {
+            func: Rc::new(Function::new(ast, None)),
+            ip: 0,
+            env: self.globals.clone(),
+            elided: 0,
+            entry: None,
+        });
+        self.execute(stack, base).map_err(|e| self.unwind(e, base))
+    }
+
+    fn execute(&mut self, stack: &mut Vec<Value>, base: usize) -> JSLResult<()> {
+        'frames: while self.frames.len() > base {
+            let frame = self.frames.last().unwrap();
+            let func = frame.func.clone();
+            let env = frame.env.clone();
+            let chunk = func.chunk();
+            let mut ip = frame.ip;
+            while let Some(&instr) = chunk.code.get(ip) {
+                ip += 1;
+                match instr {
+                    Instr::Push(i) => stack.push(chunk.constants[i].clone()),
+                    Instr::Load(id) => stack.push(env.get(id)),
+                    Instr::Store(id) => env.set(id, stack.pop().unwrap_or(Value::Null)),
+                    Instr::Closure(i) => stack.push(Value::Function(Closure {
+                        func: chunk.functions[i].clone(),
+                        env: env.clone(),
+                    })),
+                    Instr::List(i) => {
+                        let mut items = vec![];
+                        self.run_list(chunk.functions[i].clone(), env.clone(), &mut items)?;
+                        stack.push(Value::list(items));
+                    }
+                    Instr::Call { tail, name } => {
+                        let span = chunk.spans[ip - 1];
+                        let closure = match stack.pop().unwrap_or(Value::Null) {
+                            Value::Function(closure) => closure,
+                            // rust functions don't need a frame, they just run
+                            Value::Native(native) => {
+                                native.call(stack, span)?;
+                                continue;
+                            }
+                            _ => return Err(JSLError::new("invalid function").at(span)),
+                        };
+                        let frame = self.frames.last_mut().unwrap();
+                        // every call gets a fresh scope inside the one the function was written in
+                        let env = Scope::child(&closure.env);
+                        if tail {
+                            // tail recursion! reuse the frame
+                            frame.func = closure.func;
+                            frame.env = env;
+                            frame.ip = 0;
+                            frame.elided += 1;
+                        } else {
+                            frame.ip = ip;
+                            let entry = TraceFrame::Call {
+                                name: name.map(symbol_name),
+                                defined_at: closure.func.span,
+                                call_site: span,
+                            };
+                            self.frames.push(Frame {
+                                func: closure.func,
+                                ip: 0,
+                                env,
+                                elided: 0,
+                                entry: Some(entry),
+                            });
+                        }
+                        continue 'frames;
+                    }
+                    Instr::Primitive(p) => {
+                        self.site = chunk.spans[ip - 1];
+                        run_primitive(p, stack, self).map_err(|e| e.at(chunk.spans[ip - 1]))?
+                    }
+                }
+            }
+            // fell off the end of the function, so return
+            self.frames.pop();
+        }
+        Ok(())
+    }
+
+    // primitives calling functions run them to completion on top of whatever is running already
+    fn call_closure(&mut self, closure: Closure, stack: &mut Vec<Value>) -> JSLResult<()> {
+        let base = self.frames.len();
+        let site = self.site;
+        let entry = TraceFrame::Call {
+            name: None,
+            defined_at: closure.func.span,
+            call_site: site,
+        };
+        self.frames.push(Frame {
+            env: Scope::child(&closure.env),
+            func: closure.func,
+            ip: 0,
+            elided: 0,
+            entry: Some(entry),
+        });
+        let result = self.execute(stack, base).map_err(|e| self.unwind(e, base));
+        // the primitives in there moved the site, and the primitive that called us might call again
+        self.site = site;
+        result
+    }
+
+    // list bodies aren't calls, so they don't get a new scope (or show up in traces), just
+    // their own stack. if they error, their frames get unwound along with everything else
+    fn run_list(&mut self, body: Rc<Function>, env: Env, items: &mut Vec<Value>) -> JSLResult<()> {
+        let base = self.frames.len();
+        self.frames.push(Frame {
+            func: body,
+            ip: 0,
+            env,
+            elided: 0,
+            entry: None,
+        });
+        self.execute(items, base)
+    }
+
+    // throws away the frames an error went through, writing them down on the way out
+    fn unwind(&mut self, mut error: JSLError, base: usize) -> JSLError {
+        while self.frames.len() > base {
+            let frame = self.frames.pop().unwrap();
+            if frame.elided > 0 {
+                error = error.traced(TraceFrame::Elided(frame.elided));
+            }
+            if let Some(entry) = frame.entry {
+                error = error.traced(entry);
+            }
+        }
+        error
+    }
+}
+
+impl Caller for Vm {
+    fn call(&mut self, f: Value, stack: &mut Vec<Value>) -> JSLResult<()> {
+        match f {
+            Value::Function(closure) => self.call_closure(closure, stack),
+            Value::Native(native) => native.call(stack, self.site),
+            _ => Err(JSLError::new("invalid function")),
+        }
+    }
+
+    fn io(&mut self) -> &mut Io {
+        &mut self.io
+    }
+}
@@ -0,0 +1,103 @@
+// the vm and the tree walker are supposed to be interchangeable, so everything here has to come
+// out exactly the same on both: the stack, what got printed, and the error (trace and all)
+use jsl::{Interpreter, JSLError, OutputBuffer, Value};
+use std::io::Cursor;
+
+const PROGRAMS: &[&str] = &[
+    // the basics
+    "1 2 + 3 × 4 ÷ 5 -",
+    "1 2 3 : ⭥ . ⇈ ⟳ ⊣ ⊢ ↕",
+    "\"hi\" \" there\" ” ¯3 ” ∞ ”",
+    "[ 1 \"two\" [ 3 ] ] ⌗ [ 5 ⍳ { 2 × } ¨ ]",
+    "[ 0 10 3 ] ⍳ ⌽ 2 ↑ [ 1 2 3 4 ] 1 ↓",
+    "\"héllo\" ≢ \"héllo\" 1 ⤉ \"héllo\" ¯1 ⤉",
+    "⊡ \"a\" 1 ⤈ 2 \"b\" ⤈ : \"a\" ⤉",
+    "\"12.5\" ⍎ \"-3\" ⍎ \"5 # hi\" ⍎ \"nope\" ⍎ 1 ⍕ ⍰",
+    "1 0 ÷ 0 0 ÷ ¯1 √ 7 2 % 2 10 ^",
+    // functions, closures and tail calls
+    "{ →n { n + } } →adder 3 adder! →addt 4 addt!",
+    "{ →n n 0 = { \"done\" } { n 1 - count! } ? } →count 10000 count!",
+    "{ →n n 0 = { 0 } { n 1 - sum! n + } ? } →sum 100 sum!",
+    "{ { 1 + } →inc 0 inc! inc! } →two 3 { two! } ⍣",
+    "[ 1 2 3 4 ] { 2 % 0 = } ⌿ [ 1 2 3 ] { + } /",
+    "0 { : 5 < } { : ↗ 1 + } ↻",
+    "1 2 { 10 × } ⊸ 3 { 1 + } ⊙",
+    // output and input
+    "\"a\" ↗ 1 ↗ [ 1 2 ] ↗ ⊡ ↗",
+    "⍞ ⍠ ⍠ ⍗ ⍞",
+    "{ ⍞ : ∅ ≠ } { ⇡ ↗ } ↻",
+    // errors
+    "1 \"a\" +",
+    "[ 1 \"a\" ] { 1 + } ¨",
+    "0 { : 3 < } { 1 + : 2 = { 1 \"a\" + } { } ? } ↻",
+    "{ →n n 0 = { 1 \"a\" + } { n 1 - f! } ? } →f 5 f!",
+    "{ 1 \"a\" + } →g { g! 1 } →h { h! 1 } →k k!",
+    "[ 1 2 { [ 3 \"x\" + ] } ! ]",
+    "1e300 ⍳",
+    "[ 1 2 ] 5 ⤒",
+    "∅ !",
+    "{ \"unclosed",
+    "} 1",
+    "[ 1 2",
+    "1 2 3 4 ⎋",
+    "\"a\" ↗ 2 ⎋",
+    // rust functions
+    "3 4 hypot! [ [ 5 12 ] [ 8 15 ] ] { ⊐ hypot! } ¨",
+    "\"a\" hypot!",
+    "[ 1 \"x\" ] { hypot! } ¨",
+];
+
+struct Run {
+    stack: String,
+    output: String,
+    error: Option<String>,
+}
+
+fn run(mut jsl: Interpreter, code: &str) -> Run {
+    let output = OutputBuffer::default();
+    jsl.set_output(output.clone());
+    jsl.set_input(Cursor::new("first line\nsecond\r\nthird\n"));
+    jsl.register("hypot", |stack| match (stack.pop(), stack.pop()) {
+        (Some(Value::Number(x)), Some(Value::Number(y))) => {
+            stack.push(Value::Number(x.hypot(y)));
+            Ok(())
+        }
+        _ => Err(JSLError::new("hypot needs two numbers")),
+    });
+    let error = jsl.eval(code).err().map(|e| format!("{e:?}"));
+    Run {
+        stack: format!("{:?}", jsl.stack()),
+        output: output.take(),
+        error,
+    }
+}
+
+#[test]
+fn engines_agree() {
+    for code in PROGRAMS {
+        let vm = run(Interpreter::new(), code);
+        let walker = run(Interpreter::tree_walker(), code);
+        assert_eq!(vm.stack, walker.stack, "stacks differ for {code:?}");
+        assert_eq!(vm.output, walker.output, "output differs for {code:?}");
+        assert_eq!(vm.error, walker.error, "errors differ for {code:?}");
+    }
+}
+
+// the loop above passes just as well if nothing runs at all, so make sure some of it does
+#[test]
+fn programs_actually_run() {
+    let sum = run(
+        Interpreter::new(),
+        "{ →n n 0 = { 0 } { n 1 - sum! n + } ? } →sum 100 sum!",
+    );
+    assert_eq!(sum.stack, "[5050]");
+    let hello = run(Interpreter::tree_walker(), "\"a\" ↗ 1 ↗");
+    assert_eq!(hello.output, "a1");
+    let error = run(Interpreter::new(), "[ 1 \"a\" ] { 1 + } ¨");
+    assert!(
+        error
+            .error
+            .unwrap()
+            .contains("cannot add number and string")
+    );
+}
@@ -0,0 +1,35 @@
+// symlinks are made differently everywhere else
+#![cfg(unix)]
+
+use jsl::Interpreter;
+use std::{fs, path::PathBuf};
+
+// somewhere to make a mess in, different for every test (and every run) so they don't collide
+fn scratch_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("jsl-{name}-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+// the link is what gets deleted, not (the contents of) the directory it points to
+#[test]
+fn delete_removes_symlinks_to_directories() {
+    for (i, mut jsl) in [Interpreter::new(), Interpreter::tree_walker()]
+        .into_iter()
+        .enumerate()
+    {
+        let dir = scratch_dir(&format!("symlink{i}"));
+        let target = dir.join("target");
+        let link = dir.join("link");
+        fs::create_dir(&target).unwrap();
+        fs::write(target.join("file"), "hi").unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+
+        jsl.eval(&format!("{:?} ⌫", link.to_str().unwrap()))
+            .unwrap();
+        assert!(fs::symlink_metadata(&link).is_err());
+        assert!(target.join("file").exists());
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
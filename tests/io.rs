@@ -0,0 +1,42 @@
+use jsl::{Interpreter, Value};
+use std::io::Cursor;
+
+fn with_input(input: &[u8]) -> [Interpreter; 2] {
+    [Interpreter::new(), Interpreter::tree_walker()].map(|mut jsl| {
+        jsl.set_input(Cursor::new(input.to_vec()));
+        jsl
+    })
+}
+
+#[test]
+fn read_char_reads_one_character() {
+    for mut jsl in with_input("é😀a\n".as_bytes()) {
+        jsl.eval("⍠ ⍠ ⍠ ⍞ ⍠").unwrap();
+        assert_eq!(
+            jsl.stack(),
+            [
+                Value::string("é"),
+                Value::string("😀"),
+                Value::string("a"),
+                Value::string(""),
+                Value::Null
+            ]
+        );
+    }
+}
+
+// a byte that can't start a character errors right away, without eating what comes after it
+#[test]
+fn read_char_stops_at_bad_bytes() {
+    for bad in [0x80, 0xbf, 0xf8, 0xff] {
+        for mut jsl in with_input(&[bad, b'a', b'b', b'c', b'\n']) {
+            let error = jsl.eval("⍠").unwrap_err();
+            assert_eq!(
+                error.msg,
+                "could not read input: stream did not contain valid UTF-8"
+            );
+            jsl.eval("⍞").unwrap();
+            assert_eq!(jsl.stack(), [Value::string("abc")]);
+        }
+    }
+}
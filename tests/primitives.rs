@@ -0,0 +1,192 @@
+// what the primitives actually give back. most of these are the examples from docs.md, so if one of
+// these changes on purpose, the docs probably need to change too
+use jsl::Interpreter;
+
+// runs `code` on both engines, and checks that it leaves the same stack as `expected` does
+fn check(code: &str, expected: &str) {
+    let mut want = Interpreter::new();
+    want.eval(expected)
+        .unwrap_or_else(|e| panic!("{expected:?} failed: {e:?}"));
+    for mut jsl in [Interpreter::new(), Interpreter::tree_walker()] {
+        jsl.eval(code)
+            .unwrap_or_else(|e| panic!("{code:?} failed: {e:?}"));
+        assert_eq!(jsl.stack(), want.stack(), "wrong result for {code:?}");
+    }
+}
+
+// for things that only show up when printed
+fn check_printed(code: &str, expected: &str) {
+    for mut jsl in [Interpreter::new(), Interpreter::tree_walker()] {
+        let output = jsl::OutputBuffer::default();
+        jsl.set_output(output.clone());
+        jsl.eval(code)
+            .unwrap_or_else(|e| panic!("{code:?} failed: {e:?}"));
+        assert_eq!(output.take(), expected, "wrong output for {code:?}");
+    }
+}
+
+// runs `code` on both engines, and checks that it errors with `msg`
+fn check_error(code: &str, msg: &str) {
+    for mut jsl in [Interpreter::new(), Interpreter::tree_walker()] {
+        match jsl.eval(code) {
+            Ok(()) => panic!("{code:?} didn't error"),
+            Err(e) => assert_eq!(e.msg, msg, "wrong error for {code:?}"),
+        }
+    }
+}
+
+#[test]
+fn stack_shuffling() {
+    check("1 2 3 { 10 × } ⊸", "1 20 3");
+    check("5 { 2 × } ⊙", "10 5");
+    check("1 2 ⇈", "1 2 1");
+    check("1 2 3 ⟳", "2 3 1");
+    check("1 2 ⊣", "2");
+    check("1 2 ⊢", "2 1 2");
+    check("1 2 ⭥ :", "2 1 1");
+    check("1 2 ⊏", "1 2 [ 1 2 ]");
+    check("1 2 [ 3 4 5 ] ⊐", "3 4 5");
+}
+
+#[test]
+fn arithmetic() {
+    // the sign of % follows the number on top
+    check("¯7 2 %", "1");
+    check("7 ¯2 %", "¯1");
+    check("¯7 2 ⫽", "¯4");
+    check("2 10 ^", "1024");
+    check("3 5 ⊓ 3 5 ⊔", "3 5");
+    check("¯2.5 ≈ 2.5 ≈ ¯2.5 ⊺", "¯3 3 ¯2");
+    check("¯3 ± 0 ± ¯3 ‖", "¯1 0 3");
+    check("0 2 ○", "1");
+    check("0 ℯ", "1");
+}
+
+// ℯ counts as a letter, but it's a primitive, not part of whatever identifier it's next to
+#[test]
+fn exp_ends_identifiers() {
+    check("0 →x x ℯ", "1");
+    check("0 →x xℯ", "1");
+    check("1 0 →xℯ x", "2.718281828459045 0");
+}
+
+#[test]
+fn ranges() {
+    check("5 ⍳", "[ 0 1 2 3 4 ]");
+    check("[ 2 5 ] ⍳", "[ 2 3 4 ]");
+    check("[ 0 10 3 ] ⍳", "[ 0 3 6 9 ]");
+    check("[ 5 0 ¯2 ] ⍳", "[ 5 3 1 ]");
+    check("[ 5 0 ] ⍳ 0 ⍳", "[ ] [ ]");
+}
+
+// these used to try to allocate the whole thing and abort
+#[test]
+fn huge_ranges_error() {
+    check_error("1e300 ⍳", "⍳ range is too long");
+    check_error("1e10 ⍳", "⍳ range is too long");
+    check_error("[ 0 1 1e-300 ] ⍳", "⍳ range is too long");
+    check_error(
+        "∞ ⍳",
+        "⍳ range needs finite numbers and a step that isn't 0",
+    );
+}
+
+#[test]
+fn indexing_and_slicing() {
+    check("\"hello\" [ 1 4 ] ⤉", "\"eo\"");
+    check("[ 1 2 3 ] ¯1 ⤉ [ 1 2 3 ] 5 ⤉", "3 ∅");
+    check("[ 1 2 3 ] [ 0 9 ] ⤉", "[ 1 ∅ ]");
+    check("\"héllo\" 1 ⤉ \"héllo\" ≢", "\"é\" 5");
+    check("[ 1 2 3 4 ] 2 ↑ [ 1 2 3 4 ] 2 ↓", "[ 1 2 ] [ 3 4 ]");
+    // negative counts work from the end
+    check("[ 1 2 3 4 ] ¯2 ↑ [ 1 2 3 4 ] ¯2 ↓", "[ 3 4 ] [ 1 2 ]");
+    check("\"hello\" 10 ↑ \"hello\" ¯10 ↓", "\"hello\" \"\"");
+    check("\"hello\" 1 ¯1 ✂", "\"ell\"");
+    check("[ 1 2 3 ] ¯10 10 ✂ \"hello\" 3 1 ✂", "[ 1 2 3 ] \"\"");
+    check("[ 1 2 3 ] ⌽ \"abc\" ⌽", "[ 3 2 1 ] \"cba\"");
+}
+
+#[test]
+fn joining() {
+    // the top value goes first
+    check("\"a\" \"b\" ” [ 1 ] [ 2 ] ”", "\"ba\" [ 2 1 ]");
+    check("\"x\" ¯3 ” ∞ \"x\" ”", "\"¯3x\" \"x∞\"");
+    check("[ 1 2 ] 3 ” 0 [ 1 2 ] ”", "[ 3 1 2 ] [ 1 2 0 ]");
+    check("1 2 ,", "[ 2 1 ]");
+    check("2 { 1 + } { 10 × } ” !", "21");
+}
+
+#[test]
+fn strings() {
+    check("\"a,b,,c\" \",\" ⊘", "[ \"a\" \"b\" \"\" \"c\" ]");
+    check("\"hé\" \"\" ⊘", "[ \"h\" \"é\" ]");
+    check("[ 1 \"a\" 2 ] \"-\" ⊕", "\"1-a-2\"");
+    check("\"a-b-c\" \"-\" \"+\" ⇄", "\"a+b+c\"");
+    check("\"héllo\" \"l\" ⍸ \"hello\" \"z\" ⍸", "2 ∅");
+    check(
+        "\"hello\" \"ell\" ∊ \"hello\" \"he\" ⊲ \"hello\" \"he\" ⊳",
+        "1 1 0",
+    );
+    check(
+        "\"straße\" ⇡ \"ABC\" ⇣ \"  hi \" ⌦",
+        "\"STRASSE\" \"abc\" \"hi\"",
+    );
+    check("\"abc\" { ⊶ } ¨ 233 ⊷", "[ 97 98 99 ] \"é\"");
+    check("\"12\" ⍎ ∅ = \"twelve\" ⍎ ∅ =", "0 1");
+    check("\" -2.5 \" ⍎ \"¯1.5e3\" ⍎", "¯2.5 ¯1500");
+    // the tokenizer skips comments, ⍎ shouldn't
+    check("\"5 # hi\" ⍎ \"5#\" ⍎ \"# 5\" ⍎", "∅ ∅ ∅");
+    check("1.5 ⍕ \"hi\" ⍕ \"hi\" ⌗", "\"1.5\" \"hi\" \"\\\"hi\\\"\"");
+    check(
+        "1 ⍰ \"\" ⍰ [ ] ⍰ ⊡ ⍰ { } ⍰ ∅ ⍰",
+        "\"number\" \"string\" \"list\" \"map\" \"function\" \"null\"",
+    );
+}
+
+#[test]
+fn maps() {
+    check_printed("⊡ \"a\" 1 ⤈ \"b\" 2 ⤈ ↗", "⟨ \"a\": 1 \"b\": 2 ⟩");
+    check("⊡ \"a\" 1 ⤈ \"b\" 2 ⤈ \"a\" ⤉", "1");
+    check(
+        "⊡ \"a\" 1 ⤈ \"z\" ⤉ ⊡ \"a\" 1 ⤈ [ \"a\" \"z\" ] ⤉",
+        "∅ [ 1 ∅ ]",
+    );
+    // inserting again replaces, and keys stay sorted with numbers first
+    check(
+        "⊡ \"b\" 2 ⤈ \"a\" 1 ⤈ 3 0 ⤈ \"a\" 5 ⤈ : ⊤ ⭥ ⊥",
+        "[ 3 \"a\" \"b\" ] [ 0 5 2 ]",
+    );
+    check("⊡ 1 \"x\" ⤈ ⋈", "[ [ 1 \"x\" ] ]");
+    check("⊡ 1 1 ⤈ 2 2 ⤈ 1 ⤓ : ≢ ⭥ 2 ∋", "1 1");
+    check("⊡ ¯0 1 ⤈ 0 ⤉", "1");
+}
+
+#[test]
+fn higher_order() {
+    check("[ 1 2 ] { 10 × } ¨", "[ 10 20 ]");
+    check("\"abc\" { : } ¨", "[ \"a\" \"a\" \"b\" \"b\" \"c\" \"c\" ]");
+    check("[ 1 2 3 ] { . } ¨", "[ ]");
+    check("\"hello\" { \"l\" ≠ } ⌿", "\"heo\"");
+    check("[ 1 2 3 4 ] { 2 % 0 = } ⌿", "[ 2 4 ]");
+    check("[ 1 2 3 ] 0 { + } /", "6");
+    check("[ 1 2 3 ] 0 { + } \\", "[ 1 3 6 ]");
+    check("1 10 { 2 × } ⍣", "1024");
+    check_printed(
+        "0 { : 10 < } { : ↗ \" \" ↗ 1 + } ↻ .",
+        "0 1 2 3 4 5 6 7 8 9 ",
+    );
+}
+
+#[test]
+fn scoping() {
+    check_printed(
+        "{ →n { n + } } →adder 5 adder! →addfive 10 addfive! ↗",
+        "15",
+    );
+    check(
+        "{ →n n 0 = { \"liftoff\" } { n 1 - countdown! } ? } →countdown 3 countdown!",
+        "\"liftoff\"",
+    );
+    // bindings in a function stay in it
+    check("1 →x { 2 →x } ! x", "1");
+}
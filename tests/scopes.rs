@@ -0,0 +1,73 @@
+// a call scope that binds a local function would point at itself through that function, and
+// never get freed. to see whether it does get freed, bind something in there that counts when
+// it gets dropped
+use jsl::{Interpreter, NativeFunction, Value};
+use std::{cell::Cell, rc::Rc};
+
+struct Counted(Rc<Cell<usize>>);
+
+impl Drop for Counted {
+    fn drop(&mut self) {
+        self.0.set(self.0.get() + 1);
+    }
+}
+
+// `counted!` pushes a fresh value that bumps the returned counter once it's gone
+fn interpreters() -> Vec<(Interpreter, Rc<Cell<usize>>)> {
+    [Interpreter::new(), Interpreter::tree_walker()]
+        .into_iter()
+        .map(|mut jsl| {
+            let dropped = Rc::new(Cell::new(0));
+            let counter = dropped.clone();
+            jsl.register("counted", move |stack| {
+                let counted = Counted(counter.clone());
+                stack.push(Value::Native(Rc::new(NativeFunction::new(
+                    "counted",
+                    move |_| {
+                        let _ = &counted;
+                        Ok(())
+                    },
+                ))));
+                Ok(())
+            });
+            (jsl, dropped)
+        })
+        .collect()
+}
+
+#[test]
+fn local_helpers_dont_keep_their_scope_alive() {
+    for (mut jsl, dropped) in interpreters() {
+        jsl.eval("{ counted! →c { 1 } →helper helper! } →f f! f! f!")
+            .unwrap();
+        assert_eq!(dropped.get(), 3);
+        assert_eq!(jsl.stack(), [1.0, 1.0, 1.0].map(Value::Number));
+    }
+}
+
+#[test]
+fn returned_closures_keep_their_scope() {
+    for (mut jsl, dropped) in interpreters() {
+        jsl.eval("{ counted! →c 5 →x { x } →helper helper } →f f! →g")
+            .unwrap();
+        assert_eq!(dropped.get(), 0);
+        // the scope still has to work
+        jsl.eval("g!").unwrap();
+        assert_eq!(jsl.pop(), Some(Value::Number(5.0)));
+        // and once nothing holds the closure, it goes away as usual
+        jsl.eval("∅ →g").unwrap();
+        assert_eq!(dropped.get(), 1);
+    }
+}
+
+// errors leave calls halfway through, their scopes still have to go
+#[test]
+fn scopes_are_freed_after_errors() {
+    for (mut jsl, dropped) in interpreters() {
+        assert!(
+            jsl.eval("{ counted! →c { 1 } →helper 1 \"a\" + } →f f!")
+                .is_err()
+        );
+        assert_eq!(dropped.get(), 1);
+    }
+}
@@ -0,0 +1,65 @@
+use jsl::{Interpreter, TraceFrame};
+
+fn first_frame(mut jsl: Interpreter, code: &str) -> TraceFrame {
+    let error = jsl.eval(code).unwrap_err();
+    error.trace.into_iter().next().expect("no trace")
+}
+
+// the trace should point at the {, not at whatever the function starts with
+#[test]
+fn defined_at_is_the_function_literal() {
+    for jsl in [Interpreter::new(), Interpreter::tree_walker()] {
+        match first_frame(jsl, "  {\n 1 \"a\" + } ! 1") {
+            TraceFrame::Call {
+                defined_at: Some(at),
+                call_site,
+                ..
+            } => {
+                assert_eq!((at.line, at.col), (1, 3));
+                assert_eq!((call_site.line, call_site.col), (2, 12));
+            }
+            frame => panic!("unexpected frame {frame:?}"),
+        }
+    }
+}
+
+#[test]
+fn errors_display_with_where_they_happened() {
+    let mut jsl = Interpreter::new();
+    let error = jsl.eval("1 \"a\" +").unwrap_err();
+    assert_eq!(error.to_string(), "cannot add string and number at 1:7");
+    assert_eq!(
+        error.in_file("main.jsl").to_string(),
+        "cannot add string and number at main.jsl:1:7"
+    );
+}
+
+// so embedders can just use ? with their own errors
+#[test]
+fn errors_are_std_errors() {
+    fn run() -> Result<(), Box<dyn std::error::Error>> {
+        Interpreter::new().eval("∅ !")?;
+        Ok(())
+    }
+    assert_eq!(run().unwrap_err().to_string(), "invalid function at 1:3");
+}
+
+#[test]
+fn reports_show_the_line_and_the_trace() {
+    let code = "{ 1 \"a\" + } →g\ng! 1";
+    let error = Interpreter::new()
+        .eval(code)
+        .unwrap_err()
+        .in_file("main.jsl");
+    assert_eq!(
+        error.report(Some(code), false),
+        "error: cannot add string and number
+  --> main.jsl:1:9
+  |
+1 | { 1 \"a\" + } →g
+  |         ^
+trace (innermost first):
+  in g, called at main.jsl:2:2
+"
+    );
+}